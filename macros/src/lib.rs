@@ -0,0 +1,201 @@
+/*!
+Attribute macros for [eyre-span], re-exported from there when its `macros` feature is enabled.
+
+[eyre-span]: https://docs.rs/eyre-span/latest/eyre_span/
+*/
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use syn::{parse_macro_input, GenericArgument, ItemFn, PathArguments, ReturnType, Type};
+
+/// Like `#[tracing::instrument]`, but also emits the error if the function returns `Err`.
+///
+/// The error is emitted before it is returned, in the span it occurred in,
+/// using the level configured in `eyre_span::Builder::default_level`.
+/// A different level can be given with `emit_level`, as in `#[instrument_emit(emit_level = "warn")]`.
+/// All other arguments are passed on to `#[tracing::instrument]`.
+///
+/// The function must return `Result<T, Report>`, and the crate must depend on `tracing`.
+#[proc_macro_attribute]
+pub fn instrument_emit(
+	args: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	let (level, args) = match take_emit_level(args.into()) {
+		Ok(v) => v,
+		Err(e) => return e.into_compile_error().into(),
+	};
+	let mut item = parse_macro_input!(item as ItemFn);
+	let Some((ok, err)) = result_type(&item.sig.output) else {
+		let msg = "#[instrument_emit] requires the function to return a `Result`";
+		return syn::Error::new_spanned(&item.sig, msg)
+			.into_compile_error()
+			.into();
+	};
+	let call = call_body(&item, &ok, &err);
+	let block = quote! {{
+		let result = #call;
+		::eyre_span::__private::emit_ref(&result, #level);
+		result
+	}};
+	*item.block = syn::parse2(block).expect("generated block");
+	quote! {
+		#[::tracing::instrument(#args)]
+		#item
+	}
+	.into()
+}
+
+/// Emits the error if the function returns `Err`, and swallows it,
+/// for callbacks and event handlers whose signature does not allow returning errors.
+///
+/// A function returning `Result<(), Report>` is turned into one returning `()`,
+/// and one returning `Result<T, Report>` into one returning `Option<T>`.
+/// The error is emitted in the span it occurred in, using the level configured in
+/// `eyre_span::Builder::default_level`, or the one given with `emit_level`, as in `#[emit_errors(emit_level = "warn")]`.
+///
+/// This is named differently from `eyre_span::emit!`, since attributes and macros share a namespace.
+#[proc_macro_attribute]
+pub fn emit_errors(
+	args: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	let (level, args) = match take_emit_level(args.into()) {
+		Ok(v) => v,
+		Err(e) => return e.into_compile_error().into(),
+	};
+	if let Some(arg) = args.into_iter().next() {
+		return syn::Error::new_spanned(arg, "unknown argument")
+			.into_compile_error()
+			.into();
+	}
+	let mut item = parse_macro_input!(item as ItemFn);
+	let Some((ok, err)) = result_type(&item.sig.output) else {
+		let msg = "#[emit_errors] requires the function to return a `Result`";
+		return syn::Error::new_spanned(&item.sig, msg)
+			.into_compile_error()
+			.into();
+	};
+	let call = call_body(&item, &ok, &err);
+	let (output, value) = match ok {
+		Type::Tuple(t) if t.elems.is_empty() => (ReturnType::Default, quote! {}),
+		ok => (
+			syn::parse_quote! { -> ::core::option::Option<#ok> },
+			quote! { result.ok() },
+		),
+	};
+	let block = quote! {{
+		let result = #call;
+		::eyre_span::__private::emit_ref(&result, #level);
+		#value
+	}};
+	*item.block = syn::parse2(block).expect("generated block");
+	item.sig.output = output;
+	quote! { #item }.into()
+}
+
+/// Returns the success and error types of a function returning a `Result`.
+///
+/// If the error type is left out, as with `eyre::Result<T>`, it is assumed to be `Report`.
+fn result_type(output: &ReturnType) -> Option<(Type, TokenStream)> {
+	let ReturnType::Type(_, ty) = output else {
+		return None;
+	};
+	let Type::Path(path) = &**ty else {
+		return None;
+	};
+	let segment = path.path.segments.last()?;
+	if segment.ident != "Result" {
+		return None;
+	}
+	let PathArguments::AngleBracketed(args) = &segment.arguments else {
+		return None;
+	};
+	let mut types = args.args.iter().filter_map(|arg| match arg {
+		GenericArgument::Type(ty) => Some(ty),
+		_ => None,
+	});
+	let ok = types.next()?.clone();
+	let err = match types.next() {
+		Some(err) => quote! { #err },
+		None => quote! { ::eyre_span::__private::eyre::Report },
+	};
+	Some((ok, err))
+}
+
+/// Returns an expression that runs the function's body, with any `return`s and `?`s contained in it.
+///
+/// The body is moved into a closure or async block, so that it owns the arguments just like the function did.
+/// The types are given so that `?` knows what to convert to, and so that bodies that never return `Ok`,
+/// such as `loop { step()?; }`, still have a type.
+/// A success type containing `impl Trait` cannot be named there, so it is inferred instead.
+fn call_body(item: &ItemFn, ok: &Type, err: &TokenStream) -> TokenStream {
+	let block = &item.block;
+	let ok = match contains_impl(quote! { #ok }) {
+		true => quote! { _ },
+		false => quote! { #ok },
+	};
+	match item.sig.asyncness {
+		Some(_) => quote! {
+			::eyre_span::__private::annotate_async::<#ok, #err, _>(async move #block).await
+		},
+		None => quote! {
+			::eyre_span::__private::call_body::<#ok, #err, _>(move || #block)
+		},
+	}
+}
+
+/// Whether the tokens contain the `impl` keyword, as in an `impl Trait` type.
+fn contains_impl(tokens: TokenStream) -> bool {
+	tokens.into_iter().any(|tt| match tt {
+		TokenTree::Ident(ident) => ident == "impl",
+		TokenTree::Group(group) => contains_impl(group.stream()),
+		_ => false,
+	})
+}
+
+/// Removes the `emit_level = ...` argument, returning it as an `Option<Level>` expression.
+fn take_emit_level(args: TokenStream) -> syn::Result<(TokenStream, TokenStream)> {
+	let mut level = quote! { ::core::option::Option::None };
+	let mut rest = Vec::new();
+	for arg in split_args(args) {
+		match &arg[..] {
+			[TokenTree::Ident(name), TokenTree::Punct(eq), value @ ..]
+				if name == "emit_level" && eq.as_char() == '=' =>
+			{
+				let value: TokenStream = value.iter().cloned().collect();
+				let value = match syn::parse2::<syn::LitStr>(value.clone()) {
+					Ok(lit) => {
+						let name = match &lit.value().to_ascii_lowercase()[..] {
+							"error" => "ERROR",
+							"warn" => "WARN",
+							"info" => "INFO",
+							"debug" => "DEBUG",
+							"trace" => "TRACE",
+							_ => return Err(syn::Error::new_spanned(lit, "unknown level")),
+						};
+						let name = syn::Ident::new(name, lit.span());
+						quote! { ::eyre_span::__private::Level::#name }
+					}
+					Err(_) => value,
+				};
+				level = quote! { ::core::option::Option::Some(#value) };
+			}
+			_ => rest.push(arg.into_iter().collect::<TokenStream>()),
+		}
+	}
+	Ok((level, quote! { #(#rest),* }))
+}
+
+/// Splits attribute arguments at top-level commas. Delimited groups are single tokens, so nested commas are kept.
+fn split_args(args: TokenStream) -> Vec<Vec<TokenTree>> {
+	let mut out = vec![Vec::new()];
+	for tt in args {
+		match &tt {
+			TokenTree::Punct(p) if p.as_char() == ',' => out.push(Vec::new()),
+			_ => out.last_mut().unwrap().push(tt),
+		}
+	}
+	out.retain(|arg| !arg.is_empty());
+	out
+}
@@ -0,0 +1,185 @@
+use std::future::Future;
+use std::sync::Mutex;
+
+use eyre::{bail, eyre, Result};
+use eyre_span::{emit_errors, instrument_emit};
+use tracing::Level;
+
+static EMITTED: Mutex<Vec<(String, Level)>> = Mutex::new(Vec::new());
+static RUNNING: Mutex<()> = Mutex::new(());
+
+/// Runs `f`, returning the messages and levels of the reports it emitted.
+fn emitted(f: impl FnOnce()) -> Vec<(String, Level)> {
+	static INIT: std::sync::Once = std::sync::Once::new();
+	INIT.call_once(|| {
+		eyre_span::install_if_unset().unwrap();
+		eyre_span::on_emit(|report, level| {
+			EMITTED.lock().unwrap().push((report.to_string(), level))
+		});
+	});
+	// The observer is global, so the tests take turns.
+	let _running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+	EMITTED.lock().unwrap().clear();
+	f();
+	std::mem::take(&mut *EMITTED.lock().unwrap())
+}
+
+fn block_on<F: Future>(f: F) -> F::Output {
+	let mut f = std::pin::pin!(f);
+	let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+	loop {
+		if let std::task::Poll::Ready(v) = f.as_mut().poll(&mut cx) {
+			return v;
+		}
+	}
+}
+
+fn error(s: &str) -> (String, Level) {
+	(s.to_owned(), Level::ERROR)
+}
+
+struct Counter(u32);
+
+impl Counter {
+	#[instrument_emit(skip(self))]
+	fn get(&self) -> Result<&u32> {
+		if self.0 == 0 {
+			bail!("empty");
+		}
+		Ok(&self.0)
+	}
+
+	#[emit_errors]
+	fn get_mut(&mut self) -> Result<&mut u32> {
+		if self.0 == 0 {
+			return Err(eyre!("empty"));
+		}
+		Ok(&mut self.0)
+	}
+}
+
+#[test]
+fn methods() {
+	let mut counter = Counter(1);
+	let log = emitted(|| {
+		assert_eq!(counter.get().ok(), Some(&1));
+		*counter.get_mut().unwrap() += 1;
+		assert_eq!(counter.0, 2);
+		counter.0 = 0;
+		assert!(counter.get().is_err());
+		assert_eq!(counter.get_mut(), None);
+	});
+	assert_eq!(log, [error("empty"), error("empty")]);
+}
+
+#[instrument_emit]
+async fn parse(s: &'static str) -> Result<u32> {
+	let n: u32 = s.parse()?;
+	Ok(n)
+}
+
+#[emit_errors]
+async fn always_fails() -> Result<()> {
+	bail!("failed")
+}
+
+#[test]
+fn async_fns() {
+	let log = emitted(|| {
+		assert_eq!(block_on(parse("5")).ok(), Some(5));
+		assert!(block_on(parse("x")).is_err());
+		block_on(always_fails());
+	});
+	assert_eq!(
+		log,
+		[error("invalid digit found in string"), error("failed")]
+	);
+}
+
+#[instrument_emit]
+fn evens(n: u32) -> Result<impl Iterator<Item = u32>> {
+	if n > 10 {
+		bail!("too many");
+	}
+	// `return` in a nested closure returns from the closure, not the function.
+	Ok((0..n).filter(|x| {
+		if x % 2 == 1 {
+			return false;
+		}
+		true
+	}))
+}
+
+#[emit_errors]
+fn odds(n: u32) -> Result<impl Iterator<Item = u32>> {
+	Ok(evens(n)?.map(|x| x + 1))
+}
+
+#[test]
+fn impl_trait() {
+	let log = emitted(|| {
+		assert_eq!(evens(5).unwrap().collect::<Vec<_>>(), [0, 2, 4]);
+		assert_eq!(odds(5).unwrap().collect::<Vec<_>>(), [1, 3, 5]);
+		assert!(odds(11).is_none());
+	});
+	assert_eq!(log, [error("too many"), error("too many")]);
+}
+
+fn step(n: &mut u32) -> Result<()> {
+	*n += 1;
+	if *n == 3 {
+		bail!("stopped at {n}");
+	}
+	Ok(())
+}
+
+#[emit_errors]
+fn event_loop(n: &mut u32) -> Result<()> {
+	loop {
+		step(n)?;
+	}
+}
+
+#[instrument_emit(skip(n))]
+fn run_loop(n: &mut u32) -> Result<()> {
+	loop {
+		step(n)?;
+	}
+}
+
+#[test]
+fn infinite_loop() {
+	let log = emitted(|| {
+		let mut n = 0;
+		event_loop(&mut n);
+		assert_eq!(n, 3);
+		n = 0;
+		assert!(run_loop(&mut n).is_err());
+	});
+	assert_eq!(log, [error("stopped at 3"), error("stopped at 3")]);
+}
+
+#[instrument_emit(emit_level = "warn")]
+fn warns() -> Result<()> {
+	bail!("warning")
+}
+
+#[emit_errors(emit_level = "warn")]
+fn also_warns() -> Result<u32> {
+	bail!("also warning")
+}
+
+#[test]
+fn emit_level() {
+	let log = emitted(|| {
+		assert!(warns().is_err());
+		assert_eq!(also_warns(), None);
+	});
+	assert_eq!(
+		log,
+		[
+			("warning".to_owned(), Level::WARN),
+			("also warning".to_owned(), Level::WARN)
+		]
+	);
+}
@@ -0,0 +1,611 @@
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use eyre::Report;
+use tracing::Level;
+
+use crate::{ExitStatus, Handler, Inner, Origin, Severity};
+
+pub(crate) type LevelFn = dyn Fn(&Report) -> Level + Send + Sync;
+/// A hook as accepted by [`eyre::set_hook`], created by [`hook`](crate::hook) or [`Builder::into_hook`].
+pub type Hook = Box<
+	dyn Fn(&(dyn std::error::Error + 'static)) -> Box<dyn eyre::EyreHandler>
+		+ Send
+		+ Sync
+		+ 'static,
+>;
+pub(crate) type InnerFn =
+	dyn Fn(&(dyn std::error::Error + 'static)) -> Box<dyn eyre::EyreHandler> + Send + Sync;
+pub(crate) type SpanFormatterFn = dyn Fn(&mut dyn std::fmt::Write, &'static tracing::Metadata<'static>, &str) -> std::fmt::Result
+	+ Send
+	+ Sync;
+pub(crate) type RedactFn = dyn Fn(&str) -> String + Send + Sync;
+pub(crate) type SeverityFn = fn(&(dyn std::error::Error + 'static)) -> Option<Level>;
+pub(crate) type ExitStatusFn = fn(&(dyn std::error::Error + 'static)) -> Option<u8>;
+
+#[derive(Clone)]
+pub(crate) struct Config {
+	pub(crate) default_level: Level,
+	pub(crate) level_fn: Option<Arc<LevelFn>>,
+	pub(crate) repeat_level: Option<Level>,
+	pub(crate) severities: Vec<SeverityFn>,
+	pub(crate) exit_statuses: Vec<ExitStatusFn>,
+	pub(crate) error_field: bool,
+	pub(crate) chain_field: bool,
+	pub(crate) span_trace_field: bool,
+	pub(crate) record_on_span: bool,
+	pub(crate) mark_error: bool,
+	pub(crate) mark_error_ancestors: bool,
+	pub(crate) sections_field: bool,
+	pub(crate) origin_fields: bool,
+	#[cfg(feature = "tracing-error")]
+	pub(crate) display_span_trace: bool,
+	#[cfg(feature = "tracing-error")]
+	pub(crate) eager_span_trace: bool,
+	#[cfg(feature = "tracing-error")]
+	pub(crate) empty_span_trace: Option<&'static str>,
+	pub(crate) capture_backtrace: bool,
+	pub(crate) span_locations: bool,
+	pub(crate) outermost_first: bool,
+	pub(crate) span_limit: Option<(usize, usize)>,
+	pub(crate) hidden_targets: Vec<&'static str>,
+	pub(crate) span_level: Option<Level>,
+	pub(crate) collapse_spans: bool,
+	pub(crate) skip_spans: usize,
+	pub(crate) span_formatter: Option<Arc<SpanFormatterFn>>,
+	pub(crate) color: Option<bool>,
+	pub(crate) hyperlinks: Option<&'static str>,
+	pub(crate) redact: Option<Arc<RedactFn>>,
+	#[cfg(feature = "tracing-error")]
+	pub(crate) strip_ansi: crate::StripAnsi,
+	#[cfg(feature = "tracing-error")]
+	pub(crate) theme: crate::Theme,
+	pub(crate) inner: Option<Arc<InnerFn>>,
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Config::DEFAULT
+	}
+}
+
+impl std::fmt::Debug for Config {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let mut f = f.debug_struct("Config");
+		f.field("default_level", &self.default_level)
+			.field("level_fn", &self.level_fn.as_ref().map(|_| ..))
+			.field("repeat_level", &self.repeat_level)
+			.field("severities", &self.severities.len())
+			.field("exit_statuses", &self.exit_statuses.len())
+			.field("error_field", &self.error_field)
+			.field("chain_field", &self.chain_field)
+			.field("span_trace_field", &self.span_trace_field)
+			.field("record_on_span", &self.record_on_span)
+			.field("mark_error", &self.mark_error)
+			.field("mark_error_ancestors", &self.mark_error_ancestors)
+			.field("sections_field", &self.sections_field)
+			.field("origin_fields", &self.origin_fields)
+			.field("capture_backtrace", &self.capture_backtrace)
+			.field("span_locations", &self.span_locations)
+			.field("outermost_first", &self.outermost_first)
+			.field("span_limit", &self.span_limit)
+			.field("hidden_targets", &self.hidden_targets)
+			.field("span_level", &self.span_level)
+			.field("collapse_spans", &self.collapse_spans)
+			.field("skip_spans", &self.skip_spans)
+			.field("span_formatter", &self.span_formatter.as_ref().map(|_| ..))
+			.field("color", &self.color)
+			.field("hyperlinks", &self.hyperlinks)
+			.field("redact", &self.redact.as_ref().map(|_| ..))
+			.field("inner", &self.inner.as_ref().map(|_| ..));
+		#[cfg(feature = "tracing-error")]
+		f.field("display_span_trace", &self.display_span_trace);
+		#[cfg(feature = "tracing-error")]
+		f.field("eager_span_trace", &self.eager_span_trace);
+		#[cfg(feature = "tracing-error")]
+		f.field("empty_span_trace", &self.empty_span_trace);
+		#[cfg(feature = "tracing-error")]
+		f.field("theme", &self.theme);
+		#[cfg(feature = "tracing-error")]
+		f.field("strip_ansi", &self.strip_ansi);
+		f.finish()
+	}
+}
+
+impl Config {
+	pub(crate) const DEFAULT: Config = Config {
+		default_level: Level::ERROR,
+		level_fn: None,
+		repeat_level: None,
+		severities: Vec::new(),
+		exit_statuses: Vec::new(),
+		error_field: false,
+		chain_field: false,
+		span_trace_field: false,
+		record_on_span: false,
+		mark_error: false,
+		mark_error_ancestors: false,
+		sections_field: false,
+		origin_fields: false,
+		#[cfg(feature = "tracing-error")]
+		display_span_trace: true,
+		#[cfg(feature = "tracing-error")]
+		eager_span_trace: false,
+		#[cfg(feature = "tracing-error")]
+		empty_span_trace: Some("(no spans recorded)"),
+		capture_backtrace: false,
+		span_locations: false,
+		outermost_first: false,
+		span_limit: None,
+		hidden_targets: Vec::new(),
+		span_level: None,
+		collapse_spans: false,
+		skip_spans: 0,
+		span_formatter: None,
+		color: None,
+		hyperlinks: None,
+		redact: None,
+		#[cfg(feature = "tracing-error")]
+		strip_ansi: crate::StripAnsi::Always,
+		#[cfg(feature = "tracing-error")]
+		theme: crate::Theme::DEFAULT,
+		inner: None,
+	};
+
+	fn apply_env(&mut self) {
+		#[cfg(feature = "color")]
+		if self.color.is_none() {
+			use std::io::IsTerminal;
+			let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+			self.color = Some(!no_color && std::io::stderr().is_terminal());
+		}
+		#[cfg(feature = "tracing-error")]
+		if let Ok(value) = std::env::var("EYRE_SPAN_TRACE") {
+			self.display_span_trace = value != "0";
+		}
+		if let Some(level) = std::env::var("EYRE_SPAN_LEVEL")
+			.ok()
+			.and_then(|value| value.parse().ok())
+		{
+			self.default_level = level;
+		}
+	}
+
+	/// Whether rendered reports should be colored. Always false without the `color` feature.
+	pub(crate) fn color(&self) -> bool {
+		cfg!(feature = "color") && self.color == Some(true)
+	}
+
+	pub(crate) fn level(&self, report: &Report) -> Level {
+		match &self.level_fn {
+			Some(f) => f(report),
+			None => self.default_level,
+		}
+	}
+}
+
+thread_local! {
+	/// Set by [`Builder::install_scoped`], takes precedence over the installed configuration.
+	static SCOPED: RefCell<Option<Arc<Config>>> = const { RefCell::new(None) };
+}
+
+/// Creates a [`Builder`] for configuring the handler before installing it.
+pub fn builder() -> Builder {
+	Builder::default()
+}
+
+/// Configures the handler. Created with [`builder`].
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+	config: Config,
+}
+
+impl Builder {
+	/// Sets the level used by [`emit`](crate::emit()) and the other functions that do not specify a level.
+	///
+	/// Defaults to [`Level::ERROR`].
+	pub fn default_level(mut self, level: Level) -> Self {
+		self.config.default_level = level;
+		self
+	}
+
+	/// Decides the level used by [`emit`](crate::emit()) based on the error, for example by downcasting it.
+	///
+	/// This takes precedence over [`default_level`](Self::default_level).
+	pub fn level_fn(mut self, f: impl Fn(&Report) -> Level + Send + Sync + 'static) -> Self {
+		self.config.level_fn = Some(Arc::new(f));
+		self
+	}
+
+	/// Sets the level used when a report that was already emitted is emitted again,
+	/// as often happens when both a helper and its caller log the error.
+	///
+	/// This only ever lowers the level, and only if the report was already sent at least as severely,
+	/// so a helper's `DEBUG` log does not hide its caller's `ERROR`.
+	/// Emits whose level is disabled do not count as sent.
+	/// By default, such reports are emitted at their usual level.
+	pub fn repeat_level(mut self, level: Level) -> Self {
+		self.config.repeat_level = Some(level);
+		self
+	}
+
+	/// Registers an error type whose [`Severity`] is used by [`emit_auto`](crate::emit_auto).
+	pub fn severity<E: Severity + std::error::Error + 'static>(mut self) -> Self {
+		self.config
+			.severities
+			.push(|e| e.downcast_ref::<E>().map(E::severity));
+		self
+	}
+
+	/// Registers an error type whose [`ExitStatus`] is used by [`run`](crate::run)
+	/// and [`emit_and_exit`](crate::Emit::emit_and_exit).
+	pub fn exit_status<E: ExitStatus + std::error::Error + 'static>(mut self) -> Self {
+		self.config
+			.exit_statuses
+			.push(|e| e.downcast_ref::<E>().map(E::exit_code));
+		self
+	}
+
+	/// Whether emitted events should carry the report in an `error` field, in addition to the message.
+	///
+	/// This lets subscribers that handle [`std::error::Error`] values specially,
+	/// such as `tracing-opentelemetry`, inspect the error and its sources.
+	///
+	/// Defaults to `false`.
+	pub fn error_field(mut self, enabled: bool) -> Self {
+		self.config.error_field = enabled;
+		self
+	}
+
+	/// Whether emitted events should list the messages of the report's whole cause chain
+	/// in an `error.chain` field.
+	///
+	/// Defaults to `false`.
+	pub fn chain_field(mut self, enabled: bool) -> Self {
+		self.config.chain_field = enabled;
+		self
+	}
+
+	/// Whether emitted events should list the report's span trace in a `span_trace` field,
+	/// in the same format as the alternate `Display` implementation.
+	///
+	/// This is mainly useful for machine-readable output, such as JSON.
+	///
+	/// Defaults to `false`.
+	#[cfg(feature = "tracing-error")]
+	pub fn span_trace_field(mut self, enabled: bool) -> Self {
+		self.config.span_trace_field = enabled;
+		self
+	}
+
+	/// Whether emitting a report should also [record](crate::ReportSpan::record_error) it on the span it occurred in.
+	///
+	/// Like the event itself, this is skipped if the level the report is emitted at is disabled.
+	///
+	/// Defaults to `false`.
+	pub fn record_on_span(mut self, enabled: bool) -> Self {
+		self.config.record_on_span = enabled;
+		self
+	}
+
+	/// Whether emitting a report should also [mark](crate::ReportSpan::mark_error) the span it occurred in as errored.
+	///
+	/// Like the event itself, this is skipped if the level the report is emitted at is disabled.
+	///
+	/// Defaults to `false`.
+	pub fn mark_error(mut self, enabled: bool) -> Self {
+		self.config.mark_error = enabled;
+		self
+	}
+
+	/// Whether emitting a report should also [mark](crate::ReportSpan::mark_error_ancestors)
+	/// the span it occurred in and its ancestors as errored.
+	///
+	/// Defaults to `false`.
+	#[cfg(feature = "tracing-error")]
+	pub fn mark_error_ancestors(mut self, enabled: bool) -> Self {
+		self.config.mark_error_ancestors = enabled;
+		self
+	}
+
+	/// Whether emitted events should list the report's [notes](crate::ReportExt::note), warnings, and suggestions
+	/// in an `error.sections` field.
+	///
+	/// Defaults to `false`.
+	pub fn sections_field(mut self, enabled: bool) -> Self {
+		self.config.sections_field = enabled;
+		self
+	}
+
+	/// Whether emitted events should record the report's [origin](crate::ReportSpan::origin)
+	/// in `error.timestamp` (in milliseconds since the Unix epoch), `error.thread.id`, and `error.thread.name` fields.
+	///
+	/// Defaults to `false`.
+	pub fn origin_fields(mut self, enabled: bool) -> Self {
+		self.config.origin_fields = enabled;
+		self
+	}
+
+	/// Whether the alternate `Display` output should include the span trace.
+	///
+	/// Defaults to `true`.
+	#[cfg(feature = "tracing-error")]
+	pub fn display_span_trace(mut self, enabled: bool) -> Self {
+		self.config.display_span_trace = enabled;
+		self
+	}
+
+	/// Whether the span trace should be captured when the report is created, rather than when it is shown.
+	///
+	/// This costs some time and memory for every report, but the trace is then complete
+	/// even if the subscriber forgets about the spans' fields once they are closed.
+	///
+	/// This has no effect with the `disabled` feature.
+	///
+	/// Defaults to `false`.
+	#[cfg(feature = "tracing-error")]
+	pub fn eager_span_trace(mut self, enabled: bool) -> Self {
+		self.config.eager_span_trace = enabled;
+		self
+	}
+
+	/// A note to show in place of the span trace when the error was created outside of any span,
+	/// so that readers can tell the trace is empty rather than missing.
+	///
+	/// `None` shows nothing at all.
+	///
+	/// Defaults to `Some("(no spans recorded)")`.
+	#[cfg(feature = "tracing-error")]
+	pub fn empty_span_trace(mut self, note: Option<&'static str>) -> Self {
+		self.config.empty_span_trace = note;
+		self
+	}
+
+	/// Whether reports should capture a [`Backtrace`](std::backtrace::Backtrace),
+	/// which is shown in the `Debug` and alternate `Display` output.
+	///
+	/// As with [`Backtrace::capture`](std::backtrace::Backtrace::capture), this only has an effect
+	/// if the `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` environment variable is set.
+	/// It also has no effect with the `disabled` feature.
+	///
+	/// Defaults to `false`.
+	pub fn capture_backtrace(mut self, enabled: bool) -> Self {
+		self.config.capture_backtrace = enabled;
+		self
+	}
+
+	/// Whether each span in the span trace should show the location where it was defined.
+	///
+	/// Defaults to `false`.
+	#[cfg(feature = "tracing-error")]
+	pub fn span_locations(mut self, enabled: bool) -> Self {
+		self.config.span_locations = enabled;
+		self
+	}
+
+	/// Whether the span trace should be shown with the outermost span first, rather than the innermost.
+	///
+	/// Defaults to `false`.
+	#[cfg(feature = "tracing-error")]
+	pub fn outermost_first(mut self, enabled: bool) -> Self {
+		self.config.outermost_first = enabled;
+		self
+	}
+
+	/// Limits the span trace to the `first` and `last` spans, replacing the ones in between with a marker
+	/// saying how many were left out.
+	///
+	/// By default, all spans are shown.
+	#[cfg(feature = "tracing-error")]
+	pub fn span_limit(mut self, first: usize, last: usize) -> Self {
+		self.config.span_limit = Some((first, last));
+		self
+	}
+
+	/// Hides spans whose target starts with `prefix`, such as `"hyper::"`, from the span trace.
+	///
+	/// This can be called multiple times to hide several targets.
+	#[cfg(feature = "tracing-error")]
+	pub fn hide_span_target(mut self, prefix: &'static str) -> Self {
+		self.config.hidden_targets.push(prefix);
+		self
+	}
+
+	/// Hides spans that are more verbose than `level` from the span trace.
+	///
+	/// By default, spans of all levels are shown.
+	#[cfg(feature = "tracing-error")]
+	pub fn span_level(mut self, level: Level) -> Self {
+		self.config.span_level = Some(level);
+		self
+	}
+
+	/// Whether consecutive identical spans in the span trace, such as from recursion,
+	/// should be shown only once, along with how many there were.
+	///
+	/// Defaults to `false`.
+	#[cfg(feature = "tracing-error")]
+	pub fn collapse_spans(mut self, enabled: bool) -> Self {
+		self.config.collapse_spans = enabled;
+		self
+	}
+
+	/// Leaves the `n` innermost spans out of the span trace.
+	///
+	/// This is useful when the subscriber already shows those spans, for example because the report is
+	/// always emitted in the same span it was created in. Hidden spans are not counted.
+	///
+	/// Defaults to `0`.
+	#[cfg(feature = "tracing-error")]
+	pub fn skip_spans(mut self, n: usize) -> Self {
+		self.config.skip_spans = n;
+		self
+	}
+
+	/// Replaces the way each span in the span trace is written, which is `target::name{fields}` by default.
+	///
+	/// The function receives the span's metadata and its formatted fields.
+	/// This overrides [`span_locations`](Self::span_locations).
+	#[cfg(feature = "tracing-error")]
+	pub fn span_formatter(
+		mut self,
+		f: impl Fn(
+				&mut dyn std::fmt::Write,
+				&'static tracing::Metadata<'static>,
+				&str,
+			) -> std::fmt::Result
+			+ Send
+			+ Sync
+			+ 'static,
+	) -> Self {
+		self.config.span_formatter = Some(Arc::new(f));
+		self
+	}
+
+	/// Sets the characters used when showing the span trace.
+	#[cfg(feature = "tracing-error")]
+	pub fn theme(mut self, theme: crate::Theme) -> Self {
+		self.config.theme = theme;
+		self
+	}
+
+	/// Whether rendered reports should be colored, regardless of `NO_COLOR` and whether stderr is a terminal.
+	#[cfg(feature = "color")]
+	pub fn color(mut self, enabled: bool) -> Self {
+		self.config.color = Some(enabled);
+		self
+	}
+
+	/// Makes the [span locations](Self::span_locations) into terminal hyperlinks, when the output is colored.
+	///
+	/// In the URL `template`, `{file}` and `{line}` are replaced with the location,
+	/// for example `"vscode://file/path/to/project/{file}:{line}"`.
+	#[cfg(all(feature = "color", feature = "tracing-error"))]
+	pub fn hyperlinks(mut self, template: &'static str) -> Self {
+		self.config.hyperlinks = Some(template);
+		self
+	}
+
+	/// Transforms each span's formatted fields before they are shown, for example to hide sensitive values.
+	///
+	/// This applies everywhere the span trace is shown, including emitted events and [`ReportView`](crate::ReportView).
+	#[cfg(feature = "tracing-error")]
+	pub fn redact(mut self, f: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+		self.config.redact = Some(Arc::new(f));
+		self
+	}
+
+	/// Sets when ANSI escape codes, such as colors, are removed from span fields.
+	///
+	/// Defaults to [`StripAnsi::Always`](crate::StripAnsi::Always).
+	#[cfg(feature = "tracing-error")]
+	pub fn strip_ansi(mut self, strip: crate::StripAnsi) -> Self {
+		self.config.strip_ansi = strip;
+		self
+	}
+
+	/// Wraps another handler, such as the one from `color-eyre`, which is then used for formatting reports.
+	///
+	/// The span is still captured, so [`ReportSpan::span`](crate::ReportSpan::span) and [`emit`](crate::emit())
+	/// work as usual, but the report's `Debug` and `Display` output is left entirely to the inner handler.
+	pub fn wrap(
+		mut self,
+		hook: impl Fn(&(dyn std::error::Error + 'static)) -> Box<dyn eyre::EyreHandler>
+			+ Send
+			+ Sync
+			+ 'static,
+	) -> Self {
+		self.config.inner = Some(Arc::new(hook));
+		self
+	}
+
+	/// Creates the hook without installing it, for passing to [`eyre::set_hook`] manually.
+	///
+	/// This is where the environment variables are read, overriding the builder's settings:
+	/// `EYRE_SPAN_LEVEL` sets the [default level](Self::default_level), for example to `warn`,
+	/// and with the `tracing-error` feature, `EYRE_SPAN_TRACE=0` disables the span trace in `Display`,
+	/// as set with `display_span_trace`.
+	pub fn into_hook(self) -> Hook {
+		let config = self.finish();
+		Box::new(move |e| {
+			let config = SCOPED
+				.with(|s| s.borrow().clone())
+				.unwrap_or_else(|| config.clone());
+			// A report made from a wrapped report takes over its span, so that it survives the round trip.
+			#[cfg(not(feature = "disabled"))]
+			let span = e.downcast_ref::<crate::SpannedError>()
+				.and_then(|e| crate::try_handler(e.report()))
+				.map_or_else(tracing::Span::current, |h| h.span.clone());
+			#[cfg(feature = "disabled")]
+			let span = tracing::Span::none();
+			Box::new(Handler {
+				inner: config.inner.as_ref().map(|f| Inner(f(e))),
+				#[cfg(feature = "tracing-error")]
+				captured: (config.eager_span_trace && !cfg!(feature = "disabled"))
+					.then(|| crate::render::capture_spans(&span)),
+				#[cfg(feature = "tracing-error")]
+				rendered: std::sync::OnceLock::new(),
+				backtrace: (config.capture_backtrace && !cfg!(feature = "disabled"))
+					.then(std::backtrace::Backtrace::capture)
+					.filter(|b| b.status() == std::backtrace::BacktraceStatus::Captured),
+				origin: (!cfg!(feature = "disabled")).then(Origin::capture),
+				span,
+				config,
+				fields: Vec::new(),
+				sections: Vec::new(),
+				code: None,
+				parents: Vec::new(),
+				emitted: std::sync::atomic::AtomicU8::new(0),
+			})
+		})
+	}
+
+	/// Installs the hook into Eyre. Required for this crate to function.
+	pub fn install(self) -> Result<(), eyre::InstallError> {
+		eyre::set_hook(self.into_hook())
+	}
+
+	/// Like [`install`](Self::install), but succeeds if this crate's hook is already installed.
+	///
+	/// In that case the existing hook is kept, along with its configuration.
+	/// This only fails if another crate's hook is installed.
+	pub fn install_if_unset(self) -> Result<(), eyre::InstallError> {
+		self.install()
+			.or_else(|err| match crate::try_handler(&Report::msg("")) {
+				Some(_) => Ok(()),
+				None => Err(err),
+			})
+	}
+
+	/// Uses this configuration for reports created on the current thread, until the returned guard is dropped.
+	///
+	/// This installs the default hook first if necessary, and fails if another crate's hook is installed.
+	/// It is mainly intended for tests, which can then each use a different configuration.
+	///
+	/// As with [`into_hook`](Self::into_hook), the configuration can be overridden by environment variables.
+	pub fn install_scoped(self) -> Result<InstallGuard, eyre::InstallError> {
+		Builder::default().install_if_unset()?;
+		let config = self.finish();
+		let previous = SCOPED.with(|s| s.replace(Some(config)));
+		Ok(InstallGuard { previous })
+	}
+
+	/// Applies the environment variables, which take effect however the configuration is installed.
+	fn finish(mut self) -> Arc<Config> {
+		self.config.apply_env();
+		Arc::new(self.config)
+	}
+}
+
+/// Restores the previous configuration when dropped. Created by [`Builder::install_scoped`].
+#[must_use]
+#[derive(Debug)]
+pub struct InstallGuard {
+	previous: Option<Arc<Config>>,
+}
+
+impl Drop for InstallGuard {
+	fn drop(&mut self) {
+		SCOPED.with(|s| *s.borrow_mut() = self.previous.take());
+	}
+}
@@ -0,0 +1,97 @@
+use std::future::Future;
+use std::panic::Location;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use eyre::Report;
+use tracing::Instrument;
+
+/// Extension trait for emitting the error of a future that resolves to a `Result`.
+pub trait EmitFuture: Future + Sized {
+	/// Wraps the future so that it resolves to `Option<T>`, emitting the error with [`emit()`](crate::emit()).
+	///
+	/// The event is attributed to where this was called, rather than to where the future is polled.
+	#[track_caller]
+	fn emit<T>(self) -> Emitted<Self>
+	where
+		Self: Future<Output = Result<T, Report>>;
+}
+
+impl<F: Future> EmitFuture for F {
+	#[track_caller]
+	fn emit<T>(self) -> Emitted<Self>
+	where
+		Self: Future<Output = Result<T, Report>>,
+	{
+		Emitted {
+			future: self,
+			location: Location::caller(),
+		}
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// A future that emits the error of the future it wraps, created by [`EmitFuture::emit`].
+	#[derive(Debug, Clone)]
+	#[must_use = "futures do nothing unless you `.await` or poll them"]
+	pub struct Emitted<F> {
+		#[pin]
+		future: F,
+		location: &'static Location<'static>,
+	}
+}
+
+impl<F, T> Future for Emitted<F>
+where
+	F: Future<Output = Result<T, Report>>,
+{
+	type Output = Option<T>;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<T>> {
+		let this = self.project();
+		this.future.poll(cx).map(|result| match result {
+			Ok(v) => Some(v),
+			Err(e) => {
+				crate::emit_report_from(&e, this.location);
+				None
+			}
+		})
+	}
+}
+
+/// Like [`retry_with_emit`](crate::retry_with_emit), but for asynchronous operations.
+///
+/// Since this crate does not depend on any runtime, the delay between attempts is awaited
+/// using `sleep`, such as `tokio::time::sleep`.
+#[track_caller]
+pub fn retry_with_emit_async<T, F, Fut, S, SFut>(
+	attempts: usize,
+	backoff: Duration,
+	mut sleep: S,
+	mut f: F,
+) -> impl Future<Output = Option<T>>
+where
+	F: FnMut() -> Fut,
+	Fut: Future<Output = Result<T, Report>>,
+	S: FnMut(Duration) -> SFut,
+	SFut: Future<Output = ()>,
+{
+	let location = Location::caller();
+	let attempts = attempts.max(1);
+	async move {
+		let mut delay = backoff;
+		for attempt in 1..=attempts {
+			let span = tracing::info_span!(target: "eyre_span", "retry", attempt);
+			match f().instrument(span).await {
+				Ok(v) => return Some(v),
+				Err(e) => crate::emit_attempt(e, attempt, attempt == attempts, location),
+			}
+			if attempt != attempts {
+				sleep(delay).await;
+				delay = crate::next_delay(delay);
+			}
+		}
+		None
+	}
+}
@@ -4,124 +4,2190 @@ A more lightweight alternative to [color-eyre], which simply grants access
 to the span where an error happened, allowing them to be printed into standard logging facilityies.
 
 To use, [`install`] the handler, after which you can get the span with [`ReportSpan::span`]
-or immediately log a `Result` with [`emit`] or its method alias [`Emit::emit`].
+or immediately log a `Result` with [`emit()`] or its method alias [`Emit::emit`].
+A bare `Report` can be logged with [`EmitReport::emit`], and the errors of an iterator with [`EmitIterator`].
+Using this crate's [`WrapErr`] instead of Eyre's also records where errors were propagated.
+The extension traits and macros can all be imported at once from the [`prelude`].
+
+The handler's behavior can be configured by installing it through a [`builder`] instead.
+Some settings can also be overridden by environment variables, as described in [`Builder::into_hook`].
 
 This may not work correctly with all subscriber, but it works fine with the standard `tracing_subscriber::fmt`.
 
-If the `tracing-error` feature is enabled (default), the `Display` implementation will show a span trace.
+The alternate `Display` output (`{:#}`) shows the numbered cause chain, and
+if the `tracing-error` feature is enabled (default), a span trace.
+
+If the `tracing-opentelemetry` feature is enabled, emitted events follow the OpenTelemetry conventions for exceptions,
+carrying `exception.message` and `exception.stacktrace` fields, and errors emitted at `ERROR` level
+set `otel.status_code` and `otel.status_message` on the span they occurred in.
+As usual, those fields must be declared on the span for this to have an effect.
+The exception type is not available and is therefore not recorded.
+
+If the `color` feature is enabled, the alternate `Display` output is colored when stderr is a terminal,
+unless the `NO_COLOR` environment variable is set.
+This can be overridden with `Builder::color`.
+
+If the `futures` feature is enabled, futures that resolve to a `Result` can be emitted with `EmitFuture`.
+
+If the `macros` feature is enabled, `#[instrument_emit]` can be used in place of `#[tracing::instrument]`
+to also emit the error when the function fails,
+and `#[emit_errors]` turns a function returning a `Result` into one that emits and swallows its error.
+
+If the `disabled` feature is enabled, reports capture nothing, not even their [origin](ReportSpan::origin),
+and are not tracked when they are emitted again:
+[`ReportSpan::span`] returns a disabled span, and emitted events are sent in the current span instead.
+This allows binaries to opt out of this crate even if their dependencies use it.
+
+If the `graceful` feature is enabled, nothing panics when the handler was not installed:
+[`ReportSpan::span`] returns a disabled span, and the methods of [`ReportExt`] do nothing.
+This is intended for libraries that want to use this crate without requiring applications to install it.
 
 [color-eyre]: https://docs.rs/color-eyre/latest/color_eyre/
 */
 
+use std::collections::btree_map::{BTreeMap, Entry};
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::panic::Location;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
 use eyre::Report;
-use tracing::Span;
+use tracing::{Level, Span};
+
+mod config;
+use config::Config;
+pub use config::{builder, Builder, Hook, InstallGuard};
+
+#[cfg(feature = "tracing-error")]
+pub use tracing_error::{ErrorLayer, SpanTrace};
+
+#[cfg(feature = "tracing-error")]
+mod render;
+#[cfg(feature = "tracing-error")]
+pub use render::{StripAnsi, Theme};
+mod view;
+pub use view::{ReportView, SpanView};
+#[cfg(feature = "futures")]
+mod future;
+mod reports;
+pub use reports::Reports;
+mod spanned;
+#[cfg(feature = "macros")]
+pub use eyre_span_macros::{emit_errors, instrument_emit};
+pub use spanned::SpannedError;
+pub mod thread;
+#[cfg(feature = "futures")]
+pub use future::{retry_with_emit_async, EmitFuture, Emitted};
+
+/// The extension traits and macros, for importing them all at once with `use eyre_span::prelude::*`.
+///
+/// This also includes a [`Result`] alias, like [`eyre::Result`].
+/// [`WrapErr`] is not included, since it would conflict with [`eyre::WrapErr`] if both are imported.
+pub mod prelude {
+	#[cfg(feature = "futures")]
+	pub use crate::EmitFuture;
+	pub use crate::{bail_emit, emit, emit_dbg, ensure_emit};
+	pub use crate::{Emit, EmitIterator, EmitReport, ReportExt, ReportSpan, Result};
+}
+
+/// `Result<T, Report>`, like [`eyre::Result`].
+pub type Result<T, E = Report> = std::result::Result<T, E>;
+
+/// Like [`tracing::event!`], but the level does not need to be a constant.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __event {
+	(target: $target:expr, $level:expr, $($arg:tt)+) => {
+		match $level {
+			$crate::__private::Level::ERROR => $crate::__private::tracing::event!(
+				target: $target, $crate::__private::Level::ERROR, $($arg)+
+			),
+			$crate::__private::Level::WARN => $crate::__private::tracing::event!(
+				target: $target, $crate::__private::Level::WARN, $($arg)+
+			),
+			$crate::__private::Level::INFO => $crate::__private::tracing::event!(
+				target: $target, $crate::__private::Level::INFO, $($arg)+
+			),
+			$crate::__private::Level::DEBUG => $crate::__private::tracing::event!(
+				target: $target, $crate::__private::Level::DEBUG, $($arg)+
+			),
+			_ => $crate::__private::tracing::event!(
+				target: $target, $crate::__private::Level::TRACE, $($arg)+
+			),
+		}
+	};
+}
+
+/// Sends the event for an emitted report, as prepared by [`__private::emit`].
+///
+/// The module is only known if this is expanded at the call site of a macro.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __emit_event {
+	(target: $target:expr, $event:expr) => {
+		$crate::__emit_event!(target: $target, module: ::core::option::Option::None::<&str>, $event)
+	};
+	(target: $target:expr, module: $module:expr, $event:expr) => {{
+		let event: &$crate::__private::Event = $event;
+		$crate::__event!(
+			target: $target,
+			event.level,
+			code.file = event.location.file(),
+			code.line = event.location.line(),
+			code.module = $module,
+			error = event.error,
+			error.chain = event.chain.as_ref().map($crate::__private::tracing::field::debug),
+			span_trace = event.span_trace.as_ref().map($crate::__private::tracing::field::debug),
+			exception.message = event.exception.as_ref().map(|_| $crate::__private::tracing::field::display(&event.message)),
+			exception.stacktrace = event.exception.as_ref().map($crate::__private::tracing::field::display),
+			error.file = event.created_at.map(|l| l.file()),
+			error.line = event.created_at.map(|l| l.line()),
+			error.code = event.code,
+			error.timestamp = event.timestamp,
+			error.thread.id = event.thread_id.as_ref().map($crate::__private::tracing::field::debug),
+			error.thread.name = event.thread_name,
+			error.fields = event.fields.as_ref().map($crate::__private::tracing::field::display),
+			error.sections = event.sections.as_ref().map($crate::__private::tracing::field::debug),
+			error.parent_spans = event.parents.as_ref().map($crate::__private::tracing::field::debug),
+			"{}",
+			event.message,
+		)
+	}};
+}
 
 #[derive(Debug)]
 struct Handler {
 	span: Span,
+	config: Arc<Config>,
+	inner: Option<Inner>,
+	#[cfg(feature = "tracing-error")]
+	captured: Option<Vec<render::CapturedSpan>>,
+	#[cfg(feature = "tracing-error")]
+	rendered: std::sync::OnceLock<String>,
+	backtrace: Option<std::backtrace::Backtrace>,
+	/// This is `None` with the `disabled` feature.
+	origin: Option<Origin>,
+	fields: Vec<Field>,
+	sections: Vec<Section>,
+	code: Option<&'static str>,
+	parents: Vec<Span>,
+	/// The most severe level the report was sent at, as a [`level_rank`], or 0 if it was never sent.
+	emitted: std::sync::atomic::AtomicU8,
+}
+
+/// Ranks levels by severity, starting at 1 for `TRACE`.
+fn level_rank(level: Level) -> u8 {
+	match level {
+		Level::TRACE => 1,
+		Level::DEBUG => 2,
+		Level::INFO => 3,
+		Level::WARN => 4,
+		Level::ERROR => 5,
+	}
+}
+
+/// Where and when a report was created. Returned by [`ReportSpan::origin`].
+#[derive(Debug, Clone)]
+pub struct Origin {
+	time: SystemTime,
+	thread: std::thread::Thread,
+	location: Option<&'static Location<'static>>,
+	return_trace: Vec<&'static Location<'static>>,
+}
+
+impl Origin {
+	fn capture() -> Self {
+		Origin {
+			time: SystemTime::now(),
+			thread: std::thread::current(),
+			location: None,
+			return_trace: Vec::new(),
+		}
+	}
+
+	/// The location in the source code where the report was created.
+	///
+	/// This is `None` if the report was created in a way that does not track its location.
+	pub fn location(&self) -> Option<&'static Location<'static>> {
+		self.location
+	}
+
+	/// The locations where the report was wrapped with [`WrapErr`], innermost first.
+	pub fn return_trace(&self) -> &[&'static Location<'static>] {
+		&self.return_trace
+	}
+
+	/// The time the report was created.
+	pub fn time(&self) -> SystemTime {
+		self.time
+	}
+
+	/// The id of the thread the report was created on.
+	pub fn thread_id(&self) -> std::thread::ThreadId {
+		self.thread.id()
+	}
+
+	/// The name of the thread the report was created on, if it has one.
+	pub fn thread_name(&self) -> Option<&str> {
+		self.thread.name()
+	}
+}
+
+struct Inner(Box<dyn eyre::EyreHandler>);
+
+impl std::fmt::Debug for Inner {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.write_str("..")
+	}
+}
+
+struct Field {
+	key: &'static str,
+	value: Box<dyn std::fmt::Display + Send + Sync>,
+}
+
+struct Section {
+	kind: &'static str,
+	text: Box<dyn std::fmt::Display + Send + Sync>,
+}
+
+impl std::fmt::Debug for Section {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}: {}", self.kind, self.text)
+	}
+}
+
+impl std::fmt::Debug for Field {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}={}", self.key, self.value)
+	}
+}
+
+fn try_handler(report: &Report) -> Option<&Handler> {
+	report.handler().downcast_ref::<Handler>()
+}
+
+#[cfg(not(feature = "graceful"))]
+fn handler(report: &Report) -> &Handler {
+	try_handler(report).expect("eyre-span handler")
+}
+
+/// The configuration of the report's handler, or the default one if the handler was not installed.
+fn config(report: &Report) -> &Config {
+	static DEFAULT: Config = Config::DEFAULT;
+	try_handler(report).map_or(&DEFAULT, |h| &h.config)
+}
+
+/// Returns `None` only with the `graceful` feature, panics otherwise.
+fn handler_mut(report: &mut Report) -> Option<&mut Handler> {
+	let handler = report.handler_mut().downcast_mut::<Handler>();
+	#[cfg(not(feature = "graceful"))]
+	let handler = Some(handler.expect("eyre-span handler"));
+	handler
 }
 
 impl eyre::EyreHandler for Handler {
 	fn debug(
 		&self,
-		error: &dyn std::error::Error,
+		error: &(dyn std::error::Error + 'static),
 		f: &mut std::fmt::Formatter,
 	) -> std::fmt::Result {
-		std::fmt::Debug::fmt(error, f)
+		let error = spanned::unwrap(error);
+		if let Some(inner) = &self.inner {
+			return inner.0.debug(error, f);
+		}
+		// The alternate form shows the error's own `Debug` output, for inspecting its structure.
+		if f.alternate() {
+			std::fmt::Debug::fmt(error, f)?;
+		} else {
+			if let Some(code) = self.code {
+				write!(f, "[{code}] ")?;
+			}
+			write!(f, "{error}")?;
+			self.write_chain(error, f)?;
+		}
+		self.write_location(f)?;
+		#[cfg(feature = "tracing-error")]
+		if self.config.display_span_trace {
+			f.write_str(self.rendered.get_or_init(|| self.render_span_trace()))?;
+		}
+		self.write_backtrace(f)?;
+		self.write_sections(f)
 	}
 
-	#[cfg(feature = "tracing-error")]
-	fn display(&self, e: &dyn std::error::Error, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-		use std::fmt::Write;
-
+	fn display(
+		&self,
+		e: &(dyn std::error::Error + 'static),
+		f: &mut std::fmt::Formatter,
+	) -> std::fmt::Result {
+		let e = spanned::unwrap(e);
+		if let Some(inner) = &self.inner {
+			return inner.0.display(e, f);
+		}
+		if let Some(code) = self.code {
+			write!(f, "[{code}] ")?;
+		}
 		std::fmt::Display::fmt(e, f)?;
 
 		if f.alternate() {
-			let mut s = String::new();
-			tracing_error::SpanTrace::new(self.span.clone()).with_spans(|meta, fields| {
-				write!(s, "\n• {}::{}", meta.target(), meta.name()).unwrap();
-				if !fields.is_empty() {
-					write!(s, "{{{}}}", strip_ansi(fields.to_owned())).unwrap();
-				}
-				true
-			});
-			f.write_str(&s)?;
+			self.write_chain(e, f)?;
+			self.write_location(f)?;
+			#[cfg(feature = "tracing-error")]
+			if self.config.display_span_trace {
+				f.write_str(self.rendered.get_or_init(|| self.render_span_trace()))?;
+			}
+			self.write_backtrace(f)?;
+			self.write_sections(f)?;
 		}
 		Ok(())
 	}
+
+	fn track_caller(&mut self, location: &'static Location<'static>) {
+		if let Some(origin) = &mut self.origin {
+			origin.location = Some(location);
+		}
+		if let Some(inner) = &mut self.inner {
+			inner.0.track_caller(location);
+		}
+	}
 }
 
-fn strip_ansi(mut s: String) -> String {
-	let mut keep = true;
-	s.retain(|c| match c {
-		'\x1B' => {
-			keep = false;
-			false
+impl Handler {
+	/// Renders the span trace for the alternate `Display` output.
+	///
+	/// The result is cached in `rendered`, since reports are often formatted several times.
+	#[cfg(feature = "tracing-error")]
+	fn render_span_trace(&self) -> String {
+		use std::fmt::Write;
+
+		let mut s = String::new();
+		let theme = &self.config.theme;
+		let trace = tracing_error::SpanTrace::new(self.span.clone());
+		if trace.status() == tracing_error::SpanTraceStatus::UNSUPPORTED {
+			write!(
+				s,
+				"\n{}{}span trace unavailable: ErrorLayer not installed",
+				theme.indent, theme.bullet
+			)
+			.unwrap();
+			return s;
 		}
-		'm' if !keep => {
-			keep = true;
-			false
+		let lines = render::span_lines(&self.config, &self.span, Some(self), true);
+		if lines.is_empty() {
+			if let Some(note) = self.config.empty_span_trace {
+				write!(s, "\n{}{}{note}", theme.indent, theme.bullet).unwrap();
+			}
 		}
-		_ => keep,
-	});
-	s
+		for line in lines {
+			write!(s, "\n{}{}{line}", theme.indent, theme.bullet).unwrap();
+		}
+		s
+	}
+
+	fn set_span(&mut self, span: Span) {
+		#[cfg(feature = "tracing-error")]
+		{
+			self.captured = self
+				.config
+				.eager_span_trace
+				.then(|| render::capture_spans(&span));
+			self.rendered = std::sync::OnceLock::new();
+		}
+		self.span = span;
+	}
+
+	fn write_chain(
+		&self,
+		error: &(dyn std::error::Error + 'static),
+		f: &mut std::fmt::Formatter,
+	) -> std::fmt::Result {
+		let mut causes = eyre::Chain::new(error).skip(1).enumerate().peekable();
+		if causes.peek().is_some() {
+			f.write_str("\n\nCaused by:")?;
+		}
+		for (i, cause) in causes {
+			write!(f, "\n{i:>4}: {}", Paint(DIM, cause, self.config.color()))?;
+		}
+		Ok(())
+	}
+
+	fn write_location(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let Some(origin) = &self.origin else {
+			return Ok(());
+		};
+		if let Some(location) = origin.location {
+			write!(f, "\nat {}:{}", location.file(), location.line())?;
+		}
+		for location in &origin.return_trace {
+			write!(f, "\nvia {}:{}", location.file(), location.line())?;
+		}
+		Ok(())
+	}
+
+	fn write_backtrace(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		if let Some(backtrace) = &self.backtrace {
+			write!(f, "\n\nStack backtrace:\n{backtrace}")?;
+		}
+		Ok(())
+	}
+
+	fn write_sections(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		for section in &self.sections {
+			write!(f, "\n{section:?}")?;
+		}
+		Ok(())
+	}
+}
+
+/// Warns once if span traces cannot be captured, since they would otherwise silently be empty.
+#[cfg(all(feature = "tracing-error", not(feature = "disabled")))]
+fn check_error_layer(span: &Span) {
+	static WARNED: std::sync::Once = std::sync::Once::new();
+	let status = tracing_error::SpanTrace::new(span.clone()).status();
+	if status == tracing_error::SpanTraceStatus::UNSUPPORTED {
+		WARNED.call_once(|| {
+			tracing::warn!(
+				target: "eyre_span",
+				"span traces are unavailable because tracing_error::ErrorLayer is not installed"
+			)
+		});
+	}
+}
+
+#[cfg(feature = "tracing-error")]
+const BOLD: &str = "1";
+const DIM: &str = "2";
+
+/// Wraps a value in an ANSI escape sequence, if enabled.
+struct Paint<'a, T>(&'a str, T, bool);
+
+impl<T: Display> Display for Paint<'_, T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self.2 {
+			true => write!(f, "\x1B[{}m{}\x1B[0m", self.0, self.1),
+			false => self.1.fmt(f),
+		}
+	}
 }
 
 mod seal {
 	pub trait Sealed {}
 }
 
-impl<T> seal::Sealed for eyre::Result<T> {}
+impl<T, E> seal::Sealed for Result<T, E> {}
 impl seal::Sealed for Report {}
 
 /// Extension trait for the [`span`](ReportSpan::span) method.
 pub trait ReportSpan: seal::Sealed {
 	/// Returns the span the error occurred in.
 	///
-	/// Panics if the handler was not installed.
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled,
+	/// in which case a disabled span is returned.
 	fn span(&self) -> &Span;
+
+	/// Like [`span`](ReportSpan::span), but returns `None` if the handler was not installed.
+	fn try_span(&self) -> Option<&Span>;
+
+	/// Returns where, when, and on which thread the report was created,
+	/// or `None` if the handler was not installed or the `disabled` feature is enabled.
+	fn origin(&self) -> Option<&Origin>;
+
+	/// Returns the spans attached with [`ReportExt::with_parent_span`].
+	///
+	/// Returns an empty slice if the handler was not installed.
+	fn parent_spans(&self) -> &[Span];
+
+	/// Returns the metadata of the span the error occurred in.
+	///
+	/// Returns `None` if the handler was not installed, or if the error occurred outside of any enabled span.
+	fn metadata(&self) -> Option<&'static tracing::Metadata<'static>>;
+
+	/// Returns the target of the span the error occurred in. See [`metadata`](ReportSpan::metadata).
+	fn target(&self) -> Option<&'static str> {
+		self.metadata().map(|meta| meta.target())
+	}
+
+	/// Returns the name of the span the error occurred in. See [`metadata`](ReportSpan::metadata).
+	fn name(&self) -> Option<&'static str> {
+		self.metadata().map(|meta| meta.name())
+	}
+
+	/// Returns a [`SpanTrace`] of the span the error occurred in, for rendering it yourself
+	/// or passing it on to other tools such as [`tracing_error::TracedError`].
+	///
+	/// The trace is always resolved lazily, even if [`eager_span_trace`](Builder::eager_span_trace) is set.
+	///
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled,
+	/// in which case the trace is empty.
+	#[cfg(feature = "tracing-error")]
+	fn span_trace(&self) -> SpanTrace;
+
+	/// Returns the entries of the span trace, starting with the span the error occurred in.
+	///
+	/// Unlike the `Display` output, spans are not filtered, reordered, or collapsed,
+	/// though their fields are still redacted as set with `Builder::redact`.
+	/// It is always empty if the `tracing-error` feature is disabled.
+	///
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled, in which case it is empty.
+	fn spans(&self) -> std::vec::IntoIter<SpanView>;
+
+	/// Records the error message in the `error` field of the span the error occurred in.
+	///
+	/// As with [`Span::record`], this only has an effect if the span was created with an `error` field,
+	/// for example with `error = tracing::field::Empty`.
+	///
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled, in which case this does nothing.
+	fn record_error(&self);
+
+	/// Sets the `error` field of the span the error occurred in to `true`.
+	///
+	/// As with [`record_error`](ReportSpan::record_error), the span must have been created with an `error` field.
+	///
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled, in which case this does nothing.
+	fn mark_error(&self);
+
+	/// Like [`mark_error`](ReportSpan::mark_error), but also marks all of the span's ancestors
+	/// that have an `error` field.
+	///
+	/// This requires the subscriber to be based on [`tracing_subscriber::Registry`].
+	///
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled, in which case this does nothing.
+	#[cfg(feature = "tracing-error")]
+	fn mark_error_ancestors(&self);
 }
 
 impl ReportSpan for Report {
+	#[cfg(not(feature = "graceful"))]
+	fn span(&self) -> &Span {
+		&handler(self).span
+	}
+
+	#[cfg(feature = "graceful")]
 	fn span(&self) -> &Span {
-		&self
-			.handler()
-			.downcast_ref::<Handler>()
-			.expect("eyre-span handler")
-			.span
+		static NONE: Span = Span::none();
+		self.try_span().unwrap_or(&NONE)
+	}
+
+	fn try_span(&self) -> Option<&Span> {
+		try_handler(self).map(|h| &h.span)
+	}
+
+	fn origin(&self) -> Option<&Origin> {
+		try_handler(self)?.origin.as_ref()
+	}
+
+	fn parent_spans(&self) -> &[Span] {
+		try_handler(self).map_or(&[], |h| &h.parents)
+	}
+
+	fn metadata(&self) -> Option<&'static tracing::Metadata<'static>> {
+		self.try_span()?.metadata()
+	}
+
+	#[cfg(feature = "tracing-error")]
+	fn span_trace(&self) -> SpanTrace {
+		SpanTrace::new(self.span().clone())
+	}
+
+	fn spans(&self) -> std::vec::IntoIter<SpanView> {
+		view::spans(self).into_iter()
+	}
+
+	fn record_error(&self) {
+		self.span().record("error", tracing::field::display(self));
+	}
+
+	fn mark_error(&self) {
+		self.span().record("error", true);
+	}
+
+	#[cfg(feature = "tracing-error")]
+	fn mark_error_ancestors(&self) {
+		mark_error_ancestors(self.span());
 	}
 }
 
-/// Extension trait for the [`emit`](Emit::emit) method.
-pub trait Emit<T>: seal::Sealed {
-	/// Method syntax for [`emit`].
-	fn emit(self) -> Option<T>;
+#[cfg(feature = "tracing-error")]
+fn mark_error_ancestors(span: &Span) {
+	use tracing::field::Value;
+	use tracing_subscriber::registry::LookupSpan;
+
+	span.with_subscriber(|(id, dispatch)| {
+		let Some(registry) = dispatch.downcast_ref::<tracing_subscriber::Registry>() else {
+			return;
+		};
+		let Some(span) = registry.span(id) else {
+			return;
+		};
+		for span in span.scope() {
+			let fields = span.metadata().fields();
+			if let Some(field) = fields.field("error") {
+				let values = [(&field, Some(&true as &dyn Value))];
+				let values = fields.value_set(&values);
+				dispatch.record(&span.id(), &tracing::span::Record::new(&values));
+			}
+		}
+	});
 }
 
-impl<T> Emit<T> for Result<T, Report> {
-	fn emit(self) -> Option<T> {
-		emit(self)
+/// Extension trait for attaching extra information to a [`Report`], or to the error in a `Result`.
+pub trait ReportExt: seal::Sealed + Sized {
+	/// Attaches a key-value field, which is recorded on emitted events.
+	///
+	/// Since [`tracing`] requires field names to be known in advance,
+	/// all such fields are recorded together in a single `error.fields` field.
+	///
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled, in which case this does nothing.
+	fn with_field(self, key: &'static str, value: impl Display + Send + Sync + 'static) -> Self;
+
+	/// Attaches a note, which is shown in the `Debug` and alternate `Display` output.
+	///
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled, in which case this does nothing.
+	fn note(self, note: impl Display + Send + Sync + 'static) -> Self;
+
+	/// Attaches a warning, which is shown in the `Debug` and alternate `Display` output.
+	///
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled, in which case this does nothing.
+	fn warning(self, warning: impl Display + Send + Sync + 'static) -> Self;
+
+	/// Attaches a suggestion, which is shown in the `Debug` and alternate `Display` output.
+	///
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled, in which case this does nothing.
+	fn suggestion(self, suggestion: impl Display + Send + Sync + 'static) -> Self;
+
+	/// Tags the report with an error code, which is shown in the `Display` output
+	/// and recorded in the `error.code` field of emitted events.
+	///
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled, in which case this does nothing.
+	fn with_code(self, code: &'static str) -> Self;
+
+	/// Replaces the span the error occurred in with the current span.
+	///
+	/// This is useful for reports that were sent across threads or channels,
+	/// where the span they were created in is gone or meaningless.
+	///
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled, in which case this does nothing.
+	fn in_current_span(self) -> Self;
+
+	/// Attaches another span that the error belongs to, besides the one it occurred in.
+	///
+	/// This is intended for errors that pass through queues or batches, which have more than one logical parent.
+	/// The span the error occurred in is marked as [following from](Span::follows_from) the given span,
+	/// and emitted events list the names of all such spans in an `error.parent_spans` field.
+	/// They are also available from [`ReportSpan::parent_spans`].
+	///
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled, in which case this does nothing.
+	fn with_parent_span(self, span: &Span) -> Self;
+}
+
+impl ReportExt for Report {
+	fn with_field(
+		mut self,
+		key: &'static str,
+		value: impl Display + Send + Sync + 'static,
+	) -> Self {
+		if let Some(handler) = handler_mut(&mut self) {
+			handler.fields.push(Field {
+				key,
+				value: Box::new(value),
+			});
+		}
+		self
+	}
+
+	fn note(self, note: impl Display + Send + Sync + 'static) -> Self {
+		with_section(self, "Note", note)
+	}
+
+	fn warning(self, warning: impl Display + Send + Sync + 'static) -> Self {
+		with_section(self, "Warning", warning)
+	}
+
+	fn suggestion(self, suggestion: impl Display + Send + Sync + 'static) -> Self {
+		with_section(self, "Suggestion", suggestion)
+	}
+
+	fn with_code(mut self, code: &'static str) -> Self {
+		if let Some(handler) = handler_mut(&mut self) {
+			handler.code = Some(code);
+		}
+		self
+	}
+
+	fn in_current_span(mut self) -> Self {
+		if let Some(handler) = handler_mut(&mut self) {
+			if !cfg!(feature = "disabled") {
+				handler.set_span(Span::current());
+			}
+		}
+		self
+	}
+
+	fn with_parent_span(mut self, span: &Span) -> Self {
+		if let Some(handler) = handler_mut(&mut self) {
+			handler.span.follows_from(span);
+			handler.parents.push(span.clone());
+		}
+		self
 	}
 }
 
-/// Sends a [`tracing::error!`] event if an error happened.
+impl<T> ReportExt for Result<T, Report> {
+	fn with_field(self, key: &'static str, value: impl Display + Send + Sync + 'static) -> Self {
+		self.map_err(|e| e.with_field(key, value))
+	}
+
+	fn note(self, note: impl Display + Send + Sync + 'static) -> Self {
+		self.map_err(|e| e.note(note))
+	}
+
+	fn warning(self, warning: impl Display + Send + Sync + 'static) -> Self {
+		self.map_err(|e| e.warning(warning))
+	}
+
+	fn suggestion(self, suggestion: impl Display + Send + Sync + 'static) -> Self {
+		self.map_err(|e| e.suggestion(suggestion))
+	}
+
+	fn with_code(self, code: &'static str) -> Self {
+		self.map_err(|e| e.with_code(code))
+	}
+
+	fn in_current_span(self) -> Self {
+		self.map_err(|e| e.in_current_span())
+	}
+
+	fn with_parent_span(self, span: &Span) -> Self {
+		self.map_err(|e| e.with_parent_span(span))
+	}
+}
+
+fn with_section(
+	mut report: Report,
+	kind: &'static str,
+	text: impl Display + Send + Sync + 'static,
+) -> Report {
+	if let Some(handler) = handler_mut(&mut report) {
+		handler.sections.push(Section {
+			kind,
+			text: Box::new(text),
+		});
+	}
+	report
+}
+
+/// A replacement for [`eyre::WrapErr`] that also records where the error was wrapped.
 ///
-/// Panics if the handler was not installed.
-pub fn emit<T>(e: Result<T, Report>) -> Option<T> {
-	match e {
-		Ok(v) => Some(v),
-		Err(e) => {
-			e.span().in_scope(|| tracing::error!("{e}"));
-			None
+/// The locations are shown as a return trace in the `Debug` and alternate `Display` output,
+/// after the location where the report was created,
+/// and are available from [`Origin::return_trace`].
+pub trait WrapErr<T>: seal::Sealed {
+	/// Wraps the error with a new message, like [`eyre::WrapErr::wrap_err`].
+	fn wrap_err<D>(self, msg: D) -> Result<T, Report>
+	where
+		D: Display + Send + Sync + 'static;
+
+	/// Wraps the error with a lazily evaluated message, like [`eyre::WrapErr::wrap_err_with`].
+	fn wrap_err_with<D, F>(self, f: F) -> Result<T, Report>
+	where
+		D: Display + Send + Sync + 'static,
+		F: FnOnce() -> D;
+}
+
+// Report::from is called directly rather than in a closure, so that reports created
+// by the conversion get the caller's location.
+impl<T, E> WrapErr<T> for Result<T, E>
+where
+	Report: From<E>,
+{
+	#[track_caller]
+	fn wrap_err<D>(self, msg: D) -> Result<T, Report>
+	where
+		D: Display + Send + Sync + 'static,
+	{
+		match self {
+			Ok(v) => Ok(v),
+			Err(e) => Err(wrap_err(Report::from(e), msg, Location::caller())),
+		}
+	}
+
+	#[track_caller]
+	fn wrap_err_with<D, F>(self, f: F) -> Result<T, Report>
+	where
+		D: Display + Send + Sync + 'static,
+		F: FnOnce() -> D,
+	{
+		match self {
+			Ok(v) => Ok(v),
+			Err(e) => Err(wrap_err(Report::from(e), f(), Location::caller())),
 		}
 	}
 }
 
-/// Installs the hook into Eyre. Required for this crate to function.
-pub fn install() -> Result<(), eyre::InstallError> {
-	eyre::set_hook(Box::new(|_| {
-		Box::new(Handler {
-			span: tracing::Span::current(),
-		})
-	}))
+fn wrap_err(
+	report: Report,
+	msg: impl Display + Send + Sync + 'static,
+	location: &'static Location<'static>,
+) -> Report {
+	let mut report = report.wrap_err(msg);
+	// Unlike the other extension methods, this should work as a drop-in replacement even without the handler.
+	if let Some(origin) = report
+		.handler_mut()
+		.downcast_mut::<Handler>()
+		.and_then(|h| h.origin.as_mut())
+	{
+		// A report that was just created from the error already has this location.
+		if origin.location != Some(location) {
+			origin.return_trace.push(location);
+		}
+	}
+	report
+}
+
+/// An error type that knows which level it should be emitted at.
+///
+/// To be recognized by [`emit_auto`], the type must be registered with [`Builder::severity`].
+pub trait Severity {
+	/// The level this error should be emitted at.
+	fn severity(&self) -> Level;
+}
+
+/// An error type that knows which exit code the process should exit with, if it is the cause of exiting.
+///
+/// To be recognized by [`run`] and [`Emit::emit_and_exit`], the type must be registered with [`Builder::exit_status`].
+pub trait ExitStatus {
+	/// The exit code for this error.
+	fn exit_code(&self) -> u8;
+}
+
+/// Extension trait for the [`emit`](Emit::emit) method.
+///
+/// This is implemented for any `Result` whose error can be converted into a [`Report`],
+/// so errors of concrete types can be emitted without converting them first.
+pub trait Emit<T>: seal::Sealed {
+	/// Method syntax for [`emit()`].
+	fn emit(self) -> Option<T>;
+
+	/// Method syntax for [`emit_warn`].
+	fn emit_warn(self) -> Option<T>;
+
+	/// Method syntax for [`emit_info`].
+	fn emit_info(self) -> Option<T>;
+
+	/// Method syntax for [`emit_debug`].
+	fn emit_debug(self) -> Option<T>;
+
+	/// Method syntax for [`emit_auto`].
+	fn emit_auto(self) -> Option<T>;
+
+	/// Method syntax for [`emit_in`].
+	fn emit_in(self, span: &Span) -> Option<T>;
+
+	/// Method syntax for [`emit_context`].
+	fn emit_context(self, context: impl std::fmt::Display) -> Option<T>;
+
+	/// Method syntax for [`emit_throttled`].
+	fn emit_throttled(self, interval: Duration) -> Option<T>;
+
+	/// Method syntax for [`emit_once`].
+	fn emit_once(self) -> Option<T>;
+
+	/// Like [`emit()`], but returns `default` if an error happened.
+	fn emit_or(self, default: T) -> T;
+
+	/// Like [`emit()`], but returns the result of `f` if an error happened.
+	fn emit_or_else(self, f: impl FnOnce() -> T) -> T;
+
+	/// Like [`emit()`], but returns `T::default()` if an error happened.
+	fn emit_or_default(self) -> T
+	where
+		T: Default;
+
+	/// Like [`emit()`], but exits the process with the given code if an error happened.
+	///
+	/// If an error in the chain has an [`ExitStatus`], its code is used instead.
+	///
+	/// Stdout and stderr are flushed before exiting,
+	/// but buffered or non-blocking writers in the subscriber may need to be flushed separately.
+	fn emit_and_exit(self, code: i32) -> T;
+
+	/// Like [`Result::expect`], but emits the error before panicking.
+	fn emit_expect(self, msg: &str) -> T;
+
+	/// Like [`emit()`], but adds the error to a collection instead of emitting it,
+	/// such as a `Vec<Report>` or [`Reports`], so that it can be emitted later along with the others.
+	fn emit_into(self, reports: &mut impl Extend<Report>) -> Option<T>;
+
+	/// Like [`emit()`], but returns the `Result` unchanged, so that it can still be propagated.
+	fn log_err(self) -> Result<T, Report>;
+
+	/// Like [`log_err`](Emit::log_err), but sends a [`tracing::warn!`] event.
+	fn log_warn(self) -> Result<T, Report>;
+
+	/// Like [`log_err`](Emit::log_err), but sends a [`tracing::info!`] event.
+	fn log_info(self) -> Result<T, Report>;
+
+	/// Like [`log_err`](Emit::log_err), but sends a [`tracing::debug!`] event.
+	fn log_debug(self) -> Result<T, Report>;
+}
+
+// As with `WrapErr`, the conversion is done in a `#[track_caller]` function rather than a closure,
+// so that reports created by it get the caller's location.
+impl<T, E> Emit<T> for Result<T, E>
+where
+	Report: From<E>,
+{
+	#[track_caller]
+	fn emit(self) -> Option<T> {
+		emit(into_report(self))
+	}
+
+	#[track_caller]
+	fn emit_warn(self) -> Option<T> {
+		emit_warn(into_report(self))
+	}
+
+	#[track_caller]
+	fn emit_info(self) -> Option<T> {
+		emit_info(into_report(self))
+	}
+
+	#[track_caller]
+	fn emit_debug(self) -> Option<T> {
+		emit_debug(into_report(self))
+	}
+
+	#[track_caller]
+	fn emit_auto(self) -> Option<T> {
+		emit_auto(into_report(self))
+	}
+
+	#[track_caller]
+	fn emit_in(self, span: &Span) -> Option<T> {
+		emit_in(span, into_report(self))
+	}
+
+	#[track_caller]
+	fn emit_context(self, context: impl std::fmt::Display) -> Option<T> {
+		emit_context(into_report(self), context)
+	}
+
+	#[track_caller]
+	fn emit_throttled(self, interval: Duration) -> Option<T> {
+		emit_throttled(into_report(self), interval)
+	}
+
+	#[track_caller]
+	fn emit_once(self) -> Option<T> {
+		emit_once(into_report(self))
+	}
+
+	#[track_caller]
+	fn emit_or(self, default: T) -> T {
+		emit(into_report(self)).unwrap_or(default)
+	}
+
+	#[track_caller]
+	fn emit_or_else(self, f: impl FnOnce() -> T) -> T {
+		emit(into_report(self)).unwrap_or_else(f)
+	}
+
+	#[track_caller]
+	fn emit_or_default(self) -> T
+	where
+		T: Default,
+	{
+		emit(into_report(self)).unwrap_or_default()
+	}
+
+	#[track_caller]
+	fn emit_and_exit(self, code: i32) -> T {
+		match into_report(self) {
+			Ok(v) => v,
+			Err(e) => {
+				use std::io::Write;
+				let code = exit_code(&e).map_or(code, i32::from);
+				emit_report(configured_level, &e);
+				let _ = std::io::stdout().flush();
+				let _ = std::io::stderr().flush();
+				std::process::exit(code)
+			}
+		}
+	}
+
+	#[track_caller]
+	fn emit_expect(self, msg: &str) -> T {
+		match into_report(self) {
+			Ok(v) => v,
+			Err(e) => {
+				emit_report(configured_level, &e);
+				panic!("{msg}: {e:?}")
+			}
+		}
+	}
+
+	#[track_caller]
+	fn emit_into(self, reports: &mut impl Extend<Report>) -> Option<T> {
+		match into_report(self) {
+			Ok(v) => Some(v),
+			Err(e) => {
+				reports.extend([e]);
+				None
+			}
+		}
+	}
+
+	#[track_caller]
+	fn log_err(self) -> Result<T, Report> {
+		log_at(configured_level, into_report(self))
+	}
+
+	#[track_caller]
+	fn log_warn(self) -> Result<T, Report> {
+		log_at(|_| Level::WARN, into_report(self))
+	}
+
+	#[track_caller]
+	fn log_info(self) -> Result<T, Report> {
+		log_at(|_| Level::INFO, into_report(self))
+	}
+
+	#[track_caller]
+	fn log_debug(self) -> Result<T, Report> {
+		log_at(|_| Level::DEBUG, into_report(self))
+	}
+}
+
+#[track_caller]
+fn into_report<T, E>(result: Result<T, E>) -> Result<T, Report>
+where
+	Report: From<E>,
+{
+	match result {
+		Ok(v) => Ok(v),
+		Err(e) => Err(Report::from(e)),
+	}
+}
+
+/// Extension trait for emitting a bare [`Report`], without wrapping it in a `Result`.
+pub trait EmitReport: seal::Sealed {
+	/// Like [`emit()`], but for a report that is not wrapped in a `Result`.
+	fn emit(self);
+
+	/// Like [`emit_warn`], but for a report that is not wrapped in a `Result`.
+	fn emit_warn(self);
+
+	/// Like [`emit_info`], but for a report that is not wrapped in a `Result`.
+	fn emit_info(self);
+
+	/// Like [`emit_debug`], but for a report that is not wrapped in a `Result`.
+	fn emit_debug(self);
+
+	/// Like [`emit_auto`], but for a report that is not wrapped in a `Result`.
+	fn emit_auto(self);
+
+	/// Like [`emit_in`], but for a report that is not wrapped in a `Result`.
+	fn emit_in(self, span: &Span);
+}
+
+impl EmitReport for Report {
+	#[track_caller]
+	fn emit(self) {
+		emit_report(configured_level, &self)
+	}
+
+	#[track_caller]
+	fn emit_warn(self) {
+		emit_report(|_| Level::WARN, &self)
+	}
+
+	#[track_caller]
+	fn emit_info(self) {
+		emit_report(|_| Level::INFO, &self)
+	}
+
+	#[track_caller]
+	fn emit_debug(self) {
+		emit_report(|_| Level::DEBUG, &self)
+	}
+
+	#[track_caller]
+	fn emit_auto(self) {
+		emit_report(severity_level, &self)
+	}
+
+	#[track_caller]
+	fn emit_in(self, span: &Span) {
+		emit_message(configured_level, &self, Some(span), None)
+	}
+}
+
+/// Extension trait for emitting the errors of an iterator over `Result`s.
+pub trait EmitIterator: Iterator + Sized {
+	/// Emits each error with [`emit()`], and yields only the successful values.
+	///
+	/// The events are attributed to where this was called, rather than to where the iterator is consumed.
+	#[track_caller]
+	fn filter_emit<T>(self) -> FilterEmit<Self>
+	where
+		Self: Iterator<Item = Result<T, Report>>;
+
+	/// Method syntax for [`emit_all`].
+	#[track_caller]
+	fn emit_all<T>(self) -> Vec<T>
+	where
+		Self: Iterator<Item = Result<T, Report>>;
+
+	/// Like [`emit_all`](EmitIterator::emit_all), but also returns how many errors were emitted,
+	/// for deciding whether the batch as a whole failed.
+	#[track_caller]
+	fn partition_emit<T>(self) -> (Vec<T>, usize)
+	where
+		Self: Iterator<Item = Result<T, Report>>;
+}
+
+impl<I: Iterator> EmitIterator for I {
+	#[track_caller]
+	fn filter_emit<T>(self) -> FilterEmit<Self>
+	where
+		Self: Iterator<Item = Result<T, Report>>,
+	{
+		FilterEmit {
+			iter: self,
+			location: Location::caller(),
+		}
+	}
+
+	#[track_caller]
+	fn emit_all<T>(self) -> Vec<T>
+	where
+		Self: Iterator<Item = Result<T, Report>>,
+	{
+		self.filter_emit().collect()
+	}
+
+	#[track_caller]
+	fn partition_emit<T>(self) -> (Vec<T>, usize)
+	where
+		Self: Iterator<Item = Result<T, Report>>,
+	{
+		let location = Location::caller();
+		let mut values = Vec::new();
+		let mut errors = 0;
+		for item in self {
+			match item {
+				Ok(v) => values.push(v),
+				Err(e) => {
+					emit_report_from(&e, location);
+					errors += 1;
+				}
+			}
+		}
+		(values, errors)
+	}
+}
+
+/// An iterator that emits errors and yields successful values, created by [`EmitIterator::filter_emit`].
+#[derive(Debug, Clone)]
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct FilterEmit<I> {
+	iter: I,
+	location: &'static Location<'static>,
+}
+
+impl<I, T> Iterator for FilterEmit<I>
+where
+	I: Iterator<Item = Result<T, Report>>,
+{
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		for item in &mut self.iter {
+			match item {
+				Ok(v) => return Some(v),
+				Err(e) => emit_report_from(&e, self.location),
+			}
+		}
+		None
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		(0, self.iter.size_hint().1)
+	}
+}
+
+/// Returns a guard that emits the report returned by `f` when it is dropped, unless it was [disarmed](EmitGuard::disarm).
+///
+/// This is intended for cleanup and rollback paths, where the error is an early return or a panic
+/// rather than a `Result`. The report is created in the span that was current when this was called,
+/// and the event is attributed to where this was called.
+#[track_caller]
+pub fn on_error_emit<F: FnOnce() -> Report>(f: F) -> EmitGuard<F> {
+	EmitGuard {
+		f: Some(f),
+		span: Span::current(),
+		location: Location::caller(),
+	}
+}
+
+/// A guard that emits a report when dropped, created by [`on_error_emit`].
+#[must_use = "the report is emitted as soon as the guard is dropped"]
+pub struct EmitGuard<F: FnOnce() -> Report> {
+	f: Option<F>,
+	span: Span,
+	location: &'static Location<'static>,
+}
+
+impl<F: FnOnce() -> Report> EmitGuard<F> {
+	/// Drops the guard without emitting anything, for when the scope completed successfully.
+	pub fn disarm(mut self) {
+		self.f = None;
+	}
+}
+
+impl<F: FnOnce() -> Report> std::fmt::Debug for EmitGuard<F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("EmitGuard")
+			.field("armed", &self.f.is_some())
+			.field("span", &self.span)
+			.field("location", &self.location)
+			.finish()
+	}
+}
+
+impl<F: FnOnce() -> Report> Drop for EmitGuard<F> {
+	fn drop(&mut self) {
+		if let Some(f) = self.f.take() {
+			let report = self.span.in_scope(f);
+			emit_report_from(&report, self.location);
+		}
+	}
+}
+
+/// A `Result` that must be used, warning if its error is dropped without being emitted.
+///
+/// This is meant for catching swallowed errors. If an `Err` is dropped without going through
+/// [`emit`](Emittable::emit), [`into_result`](Emittable::into_result), or [`discard`](Emittable::discard),
+/// it is emitted at `WARN` level in its span, prefixed with a note that it was dropped.
+#[must_use = "the error is reported as dropped unless it is emitted or discarded"]
+pub struct Emittable<T> {
+	result: Option<Result<T, Report>>,
+	location: &'static Location<'static>,
+}
+
+impl<T> Emittable<T> {
+	/// Wraps a `Result`. A dropped error is attributed to where this was called.
+	#[track_caller]
+	pub fn new(result: Result<T, Report>) -> Self {
+		Self {
+			result: Some(result),
+			location: Location::caller(),
+		}
+	}
+
+	/// Emits the error with [`emit()`], like [`Emit::emit`].
+	#[track_caller]
+	pub fn emit(self) -> Option<T> {
+		emit(self.into_result())
+	}
+
+	/// Unwraps the `Result`, for example to propagate it with `?`.
+	pub fn into_result(mut self) -> Result<T, Report> {
+		self.result
+			.take()
+			.expect("result is only taken when consumed")
+	}
+
+	/// Drops the error without emitting anything, to show that it was ignored on purpose.
+	pub fn discard(self) -> Option<T> {
+		self.into_result().ok()
+	}
+}
+
+impl<T> From<Result<T, Report>> for Emittable<T> {
+	#[track_caller]
+	fn from(result: Result<T, Report>) -> Self {
+		Self::new(result)
+	}
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Emittable<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("Emittable")
+			.field("result", &self.result)
+			.field("location", &self.location)
+			.finish()
+	}
+}
+
+impl<T> Drop for Emittable<T> {
+	fn drop(&mut self) {
+		if let Some(Err(report)) = self.result.take() {
+			__private::emit_from(
+				&report,
+				|_| Level::WARN,
+				None,
+				Some(format_args!("dropped without being emitted")),
+				self.location,
+				|event| __emit_event!(target: "eyre_span", event),
+			)
+		}
+	}
+}
+
+/// Calls `f` up to `attempts` times until it succeeds, emitting each failure.
+///
+/// Each attempt runs in a `retry` span with an `attempt` field, starting at 1,
+/// and the attempt number is also attached to its report as with [`ReportExt::with_field`].
+/// Failed attempts are emitted at `WARN` level, except the last, which is emitted like with [`emit()`].
+/// The delay between attempts starts at `backoff` and doubles after each attempt, up to a minute.
+///
+/// `f` is always called at least once, even if `attempts` is 0.
+#[track_caller]
+pub fn retry_with_emit<T>(
+	attempts: usize,
+	backoff: Duration,
+	mut f: impl FnMut() -> Result<T, Report>,
+) -> Option<T> {
+	let location = Location::caller();
+	let attempts = attempts.max(1);
+	let mut delay = backoff;
+	for attempt in 1..=attempts {
+		let span = tracing::info_span!(target: "eyre_span", "retry", attempt);
+		match span.in_scope(&mut f) {
+			Ok(v) => return Some(v),
+			Err(e) => emit_attempt(e, attempt, attempt == attempts, location),
+		}
+		if attempt != attempts {
+			std::thread::sleep(delay);
+			delay = next_delay(delay);
+		}
+	}
+	None
+}
+
+/// Doubles the retry delay, unless it has already reached the maximum.
+fn next_delay(delay: Duration) -> Duration {
+	const MAX: Duration = Duration::from_secs(60);
+	if delay < MAX {
+		delay.saturating_mul(2).min(MAX)
+	} else {
+		delay
+	}
+}
+
+fn emit_attempt(
+	mut report: Report,
+	attempt: usize,
+	last: bool,
+	location: &'static Location<'static>,
+) {
+	// Like emit(), this should not panic if the handler was not installed.
+	if let Some(handler) = report.handler_mut().downcast_mut::<Handler>() {
+		handler.fields.push(Field {
+			key: "attempt",
+			value: Box::new(attempt),
+		});
+	}
+	let level = |report: &Report| match last {
+		true => configured_level(report),
+		false => Level::WARN,
+	};
+	__private::emit_from(
+		&report,
+		level,
+		None,
+		None,
+		location,
+		|event| __emit_event!(target: "eyre_span", event),
+	)
+}
+
+/// Emits every error with [`emit()`], and returns the successful values.
+///
+/// This is intended for batch jobs that should process everything and log the failures.
+#[track_caller]
+pub fn emit_all<T>(iter: impl IntoIterator<Item = Result<T, Report>>) -> Vec<T> {
+	iter.into_iter().filter_emit().collect()
+}
+
+/// Sends a [`tracing::error!`] event if an error happened.
+///
+/// The level can be changed with [`Builder::default_level`].
+///
+/// The location where this was called is recorded in the event's `code.file` and `code.line` fields,
+/// and the location where the report was created in `error.file` and `error.line`.
+///
+/// If the handler was not installed when the report was created, the event is sent in the current span instead,
+/// using the default configuration.
+#[track_caller]
+pub fn emit<T>(e: Result<T, Report>) -> Option<T> {
+	emit_at(configured_level, e)
+}
+
+/// Like [`emit()`], but sends a [`tracing::warn!`] event.
+#[track_caller]
+pub fn emit_warn<T>(e: Result<T, Report>) -> Option<T> {
+	emit_at(|_| Level::WARN, e)
+}
+
+/// Like [`emit()`], but sends a [`tracing::info!`] event.
+#[track_caller]
+pub fn emit_info<T>(e: Result<T, Report>) -> Option<T> {
+	emit_at(|_| Level::INFO, e)
+}
+
+/// Like [`emit()`], but sends a [`tracing::debug!`] event.
+#[track_caller]
+pub fn emit_debug<T>(e: Result<T, Report>) -> Option<T> {
+	emit_at(|_| Level::DEBUG, e)
+}
+
+/// Like [`emit()`], but uses the [`Severity`] of the first error in the chain that has one.
+///
+/// Only types registered with [`Builder::severity`] are considered.
+/// If there are none, this uses the same level as [`emit()`].
+#[track_caller]
+pub fn emit_auto<T>(e: Result<T, Report>) -> Option<T> {
+	emit_at(severity_level, e)
+}
+
+/// Like [`emit()`], but sends the event in the given span instead of the one the report was created in.
+///
+/// This is useful if that span is not the right context, for example for errors collected from worker tasks.
+#[track_caller]
+pub fn emit_in<T>(span: &Span, e: Result<T, Report>) -> Option<T> {
+	match e {
+		Ok(v) => Some(v),
+		Err(e) => {
+			emit_message(configured_level, &e, Some(span), None);
+			None
+		}
+	}
+}
+
+/// Like [`emit()`], but prefixes the message with a context string.
+///
+/// This is similar to `e.wrap_err(context).emit()`, but the report itself is left unchanged.
+/// See also the [`emit!`] macro, which accepts format arguments.
+#[track_caller]
+pub fn emit_context<T>(e: Result<T, Report>, context: impl std::fmt::Display) -> Option<T> {
+	match e {
+		Ok(v) => Some(v),
+		Err(e) => {
+			emit_message(configured_level, &e, None, Some(format_args!("{context}")));
+			None
+		}
+	}
+}
+
+/// Like [`emit()`], but emits at most one error per `interval` from each call site.
+///
+/// Any errors in between are suppressed, regardless of their contents.
+/// The next error to be emitted is preceded by an event saying how many were suppressed.
+#[track_caller]
+pub fn emit_throttled<T>(e: Result<T, Report>, interval: Duration) -> Option<T> {
+	static THROTTLES: Mutex<BTreeMap<&Location, Throttle>> = Mutex::new(BTreeMap::new());
+	struct Throttle {
+		last: Instant,
+		suppressed: usize,
+	}
+
+	let e = match e {
+		Ok(v) => return Some(v),
+		Err(e) => e,
+	};
+
+	let now = Instant::now();
+	let suppressed = {
+		let mut throttles = THROTTLES.lock().unwrap_or_else(|e| e.into_inner());
+		match throttles.entry(Location::caller()) {
+			Entry::Vacant(entry) => {
+				entry.insert(Throttle {
+					last: now,
+					suppressed: 0,
+				});
+				0
+			}
+			Entry::Occupied(mut entry) => {
+				let throttle = entry.get_mut();
+				if now.duration_since(throttle.last) < interval {
+					throttle.suppressed += 1;
+					return None;
+				}
+				throttle.last = now;
+				std::mem::take(&mut throttle.suppressed)
+			}
+		}
+	};
+
+	let level = configured_level(&e);
+	if suppressed > 0 {
+		let message =
+			|| __event!(target: "eyre_span", level, "suppressed {suppressed} similar errors");
+		// Like the error itself, this is sent in the current span if the handler was not installed.
+		match e.try_span() {
+			Some(span) => span.in_scope(message),
+			None => message(),
+		}
+	}
+	emit_report(|_| level, &e);
+	None
+}
+
+/// Like [`emit()`], but only emits the first error from each call site.
+///
+/// Any later errors from the same call site are silently discarded.
+#[track_caller]
+pub fn emit_once<T>(e: Result<T, Report>) -> Option<T> {
+	static EMITTED: Mutex<BTreeSet<&Location>> = Mutex::new(BTreeSet::new());
+
+	let e = match e {
+		Ok(v) => return Some(v),
+		Err(e) => e,
+	};
+
+	let first = EMITTED
+		.lock()
+		.unwrap_or_else(|e| e.into_inner())
+		.insert(Location::caller());
+	if first {
+		emit_report(configured_level, &e);
+	}
+	None
+}
+
+fn configured_level(report: &Report) -> Level {
+	config(report).level(report)
+}
+
+fn exit_code(report: &Report) -> Option<u8> {
+	let config = config(report);
+	report
+		.chain()
+		.find_map(|e| config.exit_statuses.iter().find_map(|f| f(e)))
+}
+
+fn severity_level(report: &Report) -> Level {
+	let config = config(report);
+	report
+		.chain()
+		.find_map(|e| config.severities.iter().find_map(|f| f(e)))
+		.unwrap_or_else(|| config.level(report))
+}
+
+#[track_caller]
+fn emit_at<T>(level: impl FnOnce(&Report) -> Level, e: Result<T, Report>) -> Option<T> {
+	match e {
+		Ok(v) => Some(v),
+		Err(e) => {
+			emit_report(level, &e);
+			None
+		}
+	}
+}
+
+#[track_caller]
+fn log_at<T>(level: impl FnOnce(&Report) -> Level, e: Result<T, Report>) -> Result<T, Report> {
+	if let Err(e) = &e {
+		emit_report(level, e);
+	}
+	e
+}
+
+#[track_caller]
+fn emit_report(level: impl FnOnce(&Report) -> Level, e: &Report) {
+	emit_message(level, e, None, None)
+}
+
+fn emit_report_from(report: &Report, location: &'static Location<'static>) {
+	__private::emit_from(
+		report,
+		configured_level,
+		None,
+		None,
+		location,
+		|event| __emit_event!(target: "eyre_span", event),
+	)
+}
+
+/// A `span` of `None` means the span the report was created in.
+#[track_caller]
+fn emit_message(
+	level: impl FnOnce(&Report) -> Level,
+	report: &Report,
+	span: Option<&Span>,
+	context: Option<std::fmt::Arguments>,
+) {
+	__private::emit(
+		report,
+		level,
+		span,
+		context,
+		|event| __emit_event!(target: "eyre_span", event),
+	)
+}
+
+/// Like [`emit()`], but the event is sent from the macro's call site,
+/// so its target and location metadata belong to the calling code rather than to this crate.
+///
+/// The message can be prefixed with a formatted context string,
+/// as in `emit!(result, "while syncing shard {}", shard_id)`.
+///
+/// The event's target defaults to the calling module,
+/// but can be set with `emit!(target: "my_app::sync", result, ...)`.
+/// It must be a constant, as with the [`tracing`] macros.
+#[macro_export]
+macro_rules! emit {
+	(target: $target:expr, $result:expr $(,)?) => {
+		$crate::__private::emit_with($result, None, |event| {
+			$crate::__emit_event!(target: $target, module: ::core::module_path!(), event)
+		})
+	};
+	(target: $target:expr, $result:expr, $($arg:tt)+) => {
+		$crate::__private::emit_with($result, Some(::core::format_args!($($arg)+)), |event| {
+			$crate::__emit_event!(target: $target, module: ::core::module_path!(), event)
+		})
+	};
+	($result:expr $(,)?) => {
+		$crate::emit!(target: ::core::module_path!(), $result)
+	};
+	($result:expr, $($arg:tt)+) => {
+		$crate::emit!(target: ::core::module_path!(), $result, $($arg)+)
+	};
+}
+
+/// Like [`emit!`], but prefixes the message with the expression's source text and location, like [`dbg!`].
+///
+/// This is useful for telling apart several similar fallible calls while debugging,
+/// as in `emit_dbg!(fetch(a))`, which emits an error like `[src/main.rs:10] fetch(a): connection refused`.
+#[macro_export]
+macro_rules! emit_dbg {
+	($result:expr $(,)?) => {
+		$crate::emit!(
+			$result,
+			"[{}:{}] {}",
+			::core::file!(),
+			::core::line!(),
+			::core::stringify!($result),
+		)
+	};
+}
+
+/// Like [`eyre::bail!`], but emits the error before returning it.
+///
+/// As with [`emit!`], the event is sent from the macro's call site.
+#[macro_export]
+macro_rules! bail_emit {
+	($($arg:tt)+) => {{
+		let report = $crate::__private::eyre::eyre!($($arg)+);
+		$crate::__private::emit_configured(&report, |event| {
+			$crate::__emit_event!(
+				target: ::core::module_path!(),
+				module: ::core::module_path!(),
+				event
+			)
+		});
+		return ::core::result::Result::Err(report);
+	}};
+}
+
+/// Like [`eyre::ensure!`], but emits the error before returning it.
+///
+/// As with [`emit!`], the event is sent from the macro's call site.
+#[macro_export]
+macro_rules! ensure_emit {
+	($cond:expr $(,)?) => {
+		if !$cond {
+			$crate::bail_emit!(::core::concat!("Condition failed: `", ::core::stringify!($cond), "`"));
+		}
+	};
+	($cond:expr, $($arg:tt)+) => {
+		if !$cond {
+			$crate::bail_emit!($($arg)+);
+		}
+	};
+}
+
+#[doc(hidden)]
+pub mod __private {
+	use std::fmt;
+
+	pub use eyre;
+	pub use tracing;
+	use tracing::level_filters::{LevelFilter, STATIC_MAX_LEVEL};
+	pub use tracing::Level;
+
+	use super::*;
+
+	pub struct Event<'a> {
+		pub level: Level,
+		pub message: Message<'a>,
+		pub location: &'static Location<'static>,
+		pub created_at: Option<&'static Location<'static>>,
+		pub error: Option<&'a (dyn std::error::Error + Send + Sync + 'static)>,
+		pub chain: Option<Chain<'a>>,
+		pub span_trace: Option<SpanTraceField<'a>>,
+		pub exception: Option<SpanTraceField<'a>>,
+		pub fields: Option<Fields<'a>>,
+		pub sections: Option<Sections<'a>>,
+		pub code: Option<&'static str>,
+		pub timestamp: Option<u64>,
+		pub thread_id: Option<std::thread::ThreadId>,
+		pub thread_name: Option<&'a str>,
+		pub parents: Option<ParentSpans<'a>>,
+	}
+
+	pub struct ParentSpans<'a>(&'a [Span]);
+
+	impl fmt::Debug for ParentSpans<'_> {
+		fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+			f.debug_list()
+				.entries(
+					self.0
+						.iter()
+						.filter_map(Span::metadata)
+						.map(|meta| format!("{}::{}", meta.target(), meta.name())),
+				)
+				.finish()
+		}
+	}
+
+	pub struct Sections<'a>(&'a [Section]);
+
+	impl fmt::Debug for Sections<'_> {
+		fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+			fmt::Debug::fmt(self.0, f)
+		}
+	}
+
+	pub struct Fields<'a>(&'a [Field]);
+
+	impl fmt::Display for Fields<'_> {
+		fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+			for (i, field) in self.0.iter().enumerate() {
+				if i != 0 {
+					f.write_str(" ")?;
+				}
+				write!(f, "{field:?}")?;
+			}
+			Ok(())
+		}
+	}
+
+	#[cfg_attr(not(feature = "tracing-error"), allow(dead_code))]
+	pub struct SpanTraceField<'a>(&'a Config, &'a Span, Option<&'a Handler>);
+
+	impl fmt::Debug for SpanTraceField<'_> {
+		fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+			let mut list = f.debug_list();
+			#[cfg(feature = "tracing-error")]
+			list.entries(crate::render::span_lines(self.0, self.1, self.2, false));
+			list.finish()
+		}
+	}
+
+	impl fmt::Display for SpanTraceField<'_> {
+		#[cfg_attr(not(feature = "tracing-error"), allow(unused_variables))]
+		fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+			#[cfg(feature = "tracing-error")]
+			f.write_str(&crate::render::span_lines(self.0, self.1, self.2, false).join("\n"))?;
+			Ok(())
+		}
+	}
+
+	pub struct Chain<'a>(&'a Report);
+
+	impl fmt::Debug for Chain<'_> {
+		fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+			f.debug_list()
+				.entries(self.0.chain().map(|e| e.to_string()))
+				.finish()
+		}
+	}
+
+	pub struct Message<'a> {
+		report: &'a Report,
+		context: Option<fmt::Arguments<'a>>,
+	}
+
+	impl fmt::Display for Message<'_> {
+		fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+			if let Some(context) = self.context {
+				write!(f, "{context}: ")?;
+			}
+			fmt::Display::fmt(self.report, f)
+		}
+	}
+
+	#[track_caller]
+	pub fn emit(
+		report: &Report,
+		level: impl FnOnce(&Report) -> Level,
+		span: Option<&Span>,
+		context: Option<fmt::Arguments>,
+		f: impl FnOnce(&Event),
+	) {
+		emit_from(report, level, span, context, Location::caller(), f)
+	}
+
+	/// Like [`emit`], but for callers that captured the location earlier, such as iterator adapters.
+	pub(crate) fn emit_from(
+		report: &Report,
+		level: impl FnOnce(&Report) -> Level,
+		span: Option<&Span>,
+		context: Option<fmt::Arguments>,
+		location: &'static Location<'static>,
+		f: impl FnOnce(&Event),
+	) {
+		let handler = crate::try_handler(report);
+		let config = crate::config(report);
+		// Reports created without the handler are emitted in the current span instead,
+		// as are all reports with the `disabled` feature, since they do not capture a span.
+		let current;
+		let report_span = match handler {
+			Some(handler) if !cfg!(feature = "disabled") => &handler.span,
+			_ => {
+				current = Span::current();
+				&current
+			}
+		};
+		let mut level = level(report);
+		// Repeats are not tracked with the `disabled` feature either.
+		let tracked = handler.filter(|_| !cfg!(feature = "disabled"));
+		if let (Some(handler), Some(repeat_level)) = (tracked, config.repeat_level) {
+			// Only lower the level if the report was already sent at least as severely.
+			let sent = handler.emitted.load(std::sync::atomic::Ordering::Relaxed);
+			if sent >= crate::level_rank(level) {
+				level = level.max(repeat_level);
+			}
+		}
+		for observer in OBSERVERS.read().unwrap_or_else(|e| e.into_inner()).iter() {
+			observer(report, level);
+		}
+		// Everything else is skipped if the event can't possibly be enabled, to keep filtered emits cheap.
+		// The event macro itself checks the callsite's interest before evaluating the message and fields.
+		if level > STATIC_MAX_LEVEL || level > LevelFilter::current() {
+			return;
+		}
+		if let Some(handler) = tracked {
+			let rank = crate::level_rank(level);
+			handler
+				.emitted
+				.fetch_max(rank, std::sync::atomic::Ordering::Relaxed);
+		}
+		if config.record_on_span {
+			report_span.record("error", tracing::field::display(report));
+		}
+		#[cfg(feature = "tracing-error")]
+		if config.mark_error_ancestors {
+			crate::mark_error_ancestors(report_span);
+		} else if config.mark_error {
+			report_span.record("error", true);
+		}
+		#[cfg(not(feature = "tracing-error"))]
+		if config.mark_error {
+			report_span.record("error", true);
+		}
+		// Reports do not capture span traces with the `disabled` feature, so whether they could is irrelevant.
+		#[cfg(all(feature = "tracing-error", not(feature = "disabled")))]
+		crate::check_error_layer(report_span);
+		#[cfg(feature = "tracing-opentelemetry")]
+		if level == Level::ERROR {
+			report_span.record("otel.status_code", "ERROR");
+			report_span.record("otel.status_message", tracing::field::display(report));
+		}
+		let origin = handler.and_then(|h| h.origin.as_ref());
+		let created_at = origin.and_then(|o| o.location);
+		let origin = origin.filter(|_| config.origin_fields);
+		let event = Event {
+			level,
+			message: Message { report, context },
+			location,
+			created_at,
+			error: config.error_field.then(|| report.as_ref()),
+			chain: config.chain_field.then_some(Chain(report)),
+			span_trace: config.span_trace_field.then_some(SpanTraceField(
+				config,
+				report_span,
+				handler,
+			)),
+			exception: cfg!(feature = "tracing-opentelemetry").then_some(SpanTraceField(
+				config,
+				report_span,
+				handler,
+			)),
+			fields: handler
+				.map(|h| &h.fields[..])
+				.filter(|f| !f.is_empty())
+				.map(Fields),
+			sections: handler
+				.map(|h| &h.sections[..])
+				.filter(|s| config.sections_field && !s.is_empty())
+				.map(Sections),
+			code: handler.and_then(|h| h.code),
+			timestamp: origin.map(|o| {
+				let since_epoch = o.time.duration_since(SystemTime::UNIX_EPOCH);
+				since_epoch.map_or(0, |d| d.as_millis() as u64)
+			}),
+			thread_id: origin.map(Origin::thread_id),
+			thread_name: origin.and_then(Origin::thread_name),
+			parents: handler
+				.map(|h| &h.parents[..])
+				.filter(|p| !p.is_empty())
+				.map(ParentSpans),
+		};
+		span.unwrap_or(report_span).in_scope(|| f(&event));
+	}
+
+	#[track_caller]
+	pub fn emit_configured(report: &Report, f: impl FnOnce(&Event)) {
+		emit(report, configured_level, None, None, f)
+	}
+
+	#[track_caller]
+	pub fn emit_ref<T>(result: &Result<T, Report>, level: Option<Level>) {
+		if let Err(e) = result {
+			match level {
+				Some(level) => emit_report(|_| level, e),
+				None => emit_report(configured_level, e),
+			}
+		}
+	}
+
+	/// Only used to let the closure's `?` operators know which type they should convert to.
+	pub fn call_body<T, E, F: FnOnce() -> Result<T, E>>(f: F) -> Result<T, E> {
+		f()
+	}
+
+	/// Only used to let the async block's `?` operators know which type they should convert to.
+	pub fn annotate_async<T, E, F: std::future::Future<Output = Result<T, E>>>(f: F) -> F {
+		f
+	}
+
+	#[track_caller]
+	pub fn emit_with<T>(
+		e: Result<T, Report>,
+		context: Option<fmt::Arguments>,
+		f: impl FnOnce(&Event),
+	) -> Option<T> {
+		match e {
+			Ok(v) => Some(v),
+			Err(e) => {
+				emit(&e, configured_level, None, context, f);
+				None
+			}
+		}
+	}
+}
+
+type Observer = dyn Fn(&Report, Level) + Send + Sync;
+static OBSERVERS: RwLock<Vec<Arc<Observer>>> = RwLock::new(Vec::new());
+
+/// Registers a function that is called for every emitted report, along with the level it is emitted at.
+///
+/// This is called regardless of whether the event is enabled,
+/// which makes it suitable for things like alerting, counting errors, or forwarding them elsewhere.
+///
+/// The observers are called while holding a lock, so they must not call this function themselves.
+pub fn on_emit(f: impl Fn(&Report, Level) + Send + Sync + 'static) {
+	OBSERVERS
+		.write()
+		.unwrap_or_else(|e| e.into_inner())
+		.push(Arc::new(f));
+}
+
+/// Installs the hook into Eyre. Required for this crate to function.
+///
+/// This is the same as `builder().install()`.
+pub fn install() -> Result<(), eyre::InstallError> {
+	builder().install()
+}
+
+/// Returns the [`ErrorLayer`] that must be added to the subscriber for span traces to be captured.
+///
+/// This saves depending on `tracing-error` directly.
+#[cfg(feature = "tracing-error")]
+pub fn layer<S>() -> ErrorLayer<S>
+where
+	S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+	ErrorLayer::default()
+}
+
+/// Sets up everything needed for this crate to work fully, in one call.
+///
+/// This [installs](install) the hook, sets `subscriber` with an added [`layer`]
+/// as the global default, and [sets a panic hook](install_panic_hook) that logs panics in the current span.
+/// Forgetting any of these does not cause errors, only less useful output.
+#[cfg(feature = "tracing-error")]
+pub fn install_all<S>(subscriber: S) -> eyre::Result<()>
+where
+	S: tracing::Subscriber
+		+ for<'a> tracing_subscriber::registry::LookupSpan<'a>
+		+ Send
+		+ Sync
+		+ 'static,
+{
+	use tracing_subscriber::layer::SubscriberExt;
+
+	install()?;
+	tracing::subscriber::set_global_default(subscriber.with(layer()))?;
+	install_panic_hook();
+	Ok(())
+}
+
+/// Sets a panic hook that logs panics as `ERROR` events in the current span, instead of printing them.
+///
+/// As with [`emit()`], the location of the panic is recorded in the event's `code.file` and `code.line` fields.
+/// This replaces any previously set panic hook.
+pub fn install_panic_hook() {
+	std::panic::set_hook(Box::new(|info| emit_panic(info.payload(), info.location())));
+}
+
+fn emit_panic(payload: &(dyn std::any::Any + Send), location: Option<&Location>) {
+	let payload = match payload.downcast_ref::<&str>() {
+		Some(s) => s,
+		None => match payload.downcast_ref::<String>() {
+			Some(s) => s.as_str(),
+			None => "Box<dyn Any>",
+		},
+	};
+	tracing::event!(
+		target: "eyre_span",
+		Level::ERROR,
+		code.file = location.map(|l| l.file()),
+		code.line = location.map(|l| l.line()),
+		"panicked: {payload}"
+	);
+}
+
+/// Like [`install`], but succeeds if this crate's hook is already installed.
+///
+/// This is useful when several crates or tests might install it.
+/// This is the same as `builder().install_if_unset()`.
+pub fn install_if_unset() -> Result<(), eyre::InstallError> {
+	builder().install_if_unset()
+}
+
+/// Runs the body of `main`, emitting the error if it fails instead of printing it to stderr.
+///
+/// This [installs](install_if_unset) the hook if necessary, and returns an exit code for `main` to return,
+/// as in `fn main() -> ExitCode { eyre_span::run(|| { ... }) }`.
+/// The exit code is 1, unless an error in the chain has an [`ExitStatus`].
+#[track_caller]
+pub fn run(f: impl FnOnce() -> eyre::Result<()>) -> std::process::ExitCode {
+	let _ = install_if_unset();
+	match f() {
+		Ok(()) => std::process::ExitCode::SUCCESS,
+		Err(e) => {
+			let code = exit_code(&e).unwrap_or(1);
+			emit_report(configured_level, &e);
+			std::process::ExitCode::from(code)
+		}
+	}
+}
+
+/// Creates the hook without installing it, for applications that call [`eyre::set_hook`] themselves.
+///
+/// This is the same as `builder().into_hook()`.
+pub fn hook() -> Hook {
+	builder().into_hook()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fmt;
+	use std::sync::{Arc, Mutex};
+
+	use tracing::level_filters::LevelFilter;
+	use tracing::{span, Metadata, Subscriber};
+
+	use super::*;
+
+	/// Collects the levels of events with the given message, with `INFO` as the maximum level.
+	struct Collect(&'static str, Arc<Mutex<Vec<Level>>>);
+
+	impl Subscriber for Collect {
+		fn enabled(&self, metadata: &Metadata) -> bool {
+			*metadata.level() <= Level::INFO
+		}
+
+		fn max_level_hint(&self) -> Option<LevelFilter> {
+			Some(LevelFilter::INFO)
+		}
+
+		fn new_span(&self, _: &span::Attributes) -> span::Id {
+			span::Id::from_u64(1)
+		}
+
+		fn record(&self, _: &span::Id, _: &span::Record) {}
+
+		fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+		fn event(&self, event: &tracing::Event) {
+			struct Message(String);
+			impl tracing::field::Visit for Message {
+				fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+					if field.name() == "message" {
+						self.0 = format!("{value:?}");
+					}
+				}
+			}
+			let mut message = Message(String::new());
+			event.record(&mut message);
+			if message.0 == self.0 {
+				self.1.lock().unwrap().push(*event.metadata().level());
+			}
+		}
+
+		fn enter(&self, _: &span::Id) {}
+
+		fn exit(&self, _: &span::Id) {}
+	}
+
+	#[test]
+	#[cfg(not(feature = "disabled"))]
+	fn repeat_level() {
+		let _guard = builder()
+			.repeat_level(Level::DEBUG)
+			.install_scoped()
+			.unwrap();
+		let levels = Arc::new(Mutex::new(Vec::new()));
+		tracing::subscriber::with_default(Collect("failed", levels.clone()), || {
+			// A filtered emit does not count as sent.
+			let report = eyre::eyre!("failed");
+			emit_report(|_| Level::DEBUG, &report);
+			emit_report(|_| Level::ERROR, &report);
+			// Once sent, it is lowered to `DEBUG` and filtered.
+			emit_report(|_| Level::ERROR, &report);
+			// A more severe emit than any sent so far is not lowered.
+			let report = eyre::eyre!("failed");
+			emit_report(|_| Level::INFO, &report);
+			emit_report(|_| Level::ERROR, &report);
+			emit_report(|_| Level::WARN, &report);
+		});
+		assert_eq!(
+			*levels.lock().unwrap(),
+			[Level::ERROR, Level::INFO, Level::ERROR]
+		);
+	}
+
+	#[test]
+	#[cfg(feature = "disabled")]
+	fn disabled() {
+		let _guard = builder()
+			.repeat_level(Level::DEBUG)
+			.install_scoped()
+			.unwrap();
+		let levels = Arc::new(Mutex::new(Vec::new()));
+		tracing::subscriber::with_default(Collect("failed", levels.clone()), || {
+			let report = eyre::eyre!("failed");
+			assert!(report.origin().is_none());
+			emit_report(|_| Level::ERROR, &report);
+			emit_report(|_| Level::ERROR, &report);
+		});
+		assert_eq!(*levels.lock().unwrap(), [Level::ERROR, Level::ERROR]);
+	}
 }
@@ -0,0 +1,293 @@
+use std::io::IsTerminal;
+
+use tracing::{Metadata, Span};
+
+use crate::{Config, Handler, Paint, BOLD, DIM};
+
+/// The characters used when showing the span trace. Set with [`Builder::theme`](crate::Builder::theme).
+#[derive(Debug, Clone)]
+pub struct Theme {
+	pub(crate) indent: &'static str,
+	pub(crate) bullet: &'static str,
+	separator: &'static str,
+	fields: bool,
+}
+
+impl Default for Theme {
+	fn default() -> Self {
+		Theme::DEFAULT
+	}
+}
+
+impl Theme {
+	pub(crate) const DEFAULT: Theme = Theme {
+		indent: "",
+		bullet: "• ",
+		separator: "::",
+		fields: true,
+	};
+
+	/// Sets the indentation before each span. Defaults to nothing.
+	pub fn indent(mut self, indent: &'static str) -> Self {
+		self.indent = indent;
+		self
+	}
+
+	/// Sets the bullet before each span, after the indentation. Defaults to `"• "`.
+	pub fn bullet(mut self, bullet: &'static str) -> Self {
+		self.bullet = bullet;
+		self
+	}
+
+	/// Sets the separator between each span's target and name. Defaults to `"::"`.
+	pub fn separator(mut self, separator: &'static str) -> Self {
+		self.separator = separator;
+		self
+	}
+
+	/// Sets whether each span's fields are shown. Defaults to `true`.
+	pub fn fields(mut self, enabled: bool) -> Self {
+		self.fields = enabled;
+		self
+	}
+}
+
+/// When to remove ANSI escape codes, such as colors, from span fields.
+/// Set with [`Builder::strip_ansi`](crate::Builder::strip_ansi).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StripAnsi {
+	/// Always remove them.
+	#[default]
+	Always,
+	/// Never remove them.
+	Never,
+	/// Keep them in the `Display` output if stderr is a terminal, and remove them otherwise.
+	Auto,
+}
+
+/// A span from a span trace that was captured when the report was created.
+#[derive(Debug)]
+pub(crate) struct CapturedSpan {
+	meta: &'static Metadata<'static>,
+	fields: String,
+}
+
+/// Renders the report's span trace, one line per span.
+///
+/// `terminal` should only be set when the lines are shown to a human,
+/// since they may then contain ANSI escape codes.
+pub(crate) fn span_lines(
+	config: &Config,
+	span: &Span,
+	handler: Option<&Handler>,
+	terminal: bool,
+) -> Vec<String> {
+	let mut spans = Vec::new();
+	with_spans(span, handler, |meta, fields| {
+		let hidden = config
+			.hidden_targets
+			.iter()
+			.any(|prefix| meta.target().starts_with(prefix));
+		if !hidden && config.span_level.is_none_or(|level| *meta.level() <= level) {
+			spans.push((meta, clean_fields(config, fields, terminal)));
+		}
+		true
+	});
+	spans.drain(..config.skip_spans.min(spans.len()));
+	if config.outermost_first {
+		spans.reverse();
+	}
+	let mut lines = spans
+		.iter()
+		.map(|(meta, fields)| {
+			let mut line = String::new();
+			write_span(&mut line, config, meta, fields, terminal && config.color()).unwrap();
+			line
+		})
+		.collect::<Vec<_>>();
+	if config.collapse_spans {
+		lines = collapse(lines);
+	}
+	if let Some((first, last)) = config.span_limit {
+		if lines.len() > first + last {
+			let omitted = lines.len() - first - last;
+			let end = lines.len() - last;
+			lines.splice(first..end, [format!("… {omitted} more spans")]);
+		}
+	}
+	lines
+}
+
+/// Replaces runs of identical lines with a single one with a count.
+fn collapse(lines: Vec<String>) -> Vec<String> {
+	let mut runs: Vec<(String, usize)> = Vec::new();
+	for line in lines {
+		match runs.last_mut() {
+			Some((last, count)) if *last == line => *count += 1,
+			_ => runs.push((line, 1)),
+		}
+	}
+	runs.into_iter()
+		.map(|(line, count)| match count {
+			1 => line,
+			n => format!("{line} ×{n}"),
+		})
+		.collect()
+}
+
+fn write_span(
+	w: &mut impl std::fmt::Write,
+	config: &Config,
+	meta: &'static Metadata<'static>,
+	fields: &str,
+	color: bool,
+) -> std::fmt::Result {
+	if let Some(f) = &config.span_formatter {
+		return f(w, meta, fields);
+	}
+	let theme = &config.theme;
+	write!(
+		w,
+		"{}{}{}",
+		meta.target(),
+		theme.separator,
+		Paint(BOLD, meta.name(), color)
+	)?;
+	if theme.fields && !fields.is_empty() {
+		write!(w, "{{{fields}}}")?;
+	}
+	if config.span_locations {
+		if let (Some(file), Some(line)) = (meta.file(), meta.line()) {
+			let location = Paint(DIM, format_args!("at {file}:{line}"), color);
+			match config.hyperlinks.filter(|_| color) {
+				Some(template) => {
+					let url = template
+						.replace("{file}", file)
+						.replace("{line}", &line.to_string());
+					write!(w, " \x1B]8;;{url}\x1B\\{location}\x1B]8;;\x1B\\")?;
+				}
+				None => write!(w, " {location}")?,
+			}
+		}
+	}
+	Ok(())
+}
+
+/// Prepares a span's formatted fields for being shown, by stripping ANSI codes and redacting them.
+pub(crate) fn clean_fields(config: &Config, fields: &str, terminal: bool) -> String {
+	let strip = match config.strip_ansi {
+		StripAnsi::Always => true,
+		StripAnsi::Never => false,
+		StripAnsi::Auto => !(terminal && std::io::stderr().is_terminal()),
+	};
+	let fields = match strip {
+		true => strip_ansi(fields.to_owned()),
+		false => fields.to_owned(),
+	};
+	match &config.redact {
+		Some(redact) => redact(&fields),
+		None => fields,
+	}
+}
+
+pub(crate) fn capture_spans(span: &Span) -> Vec<CapturedSpan> {
+	let mut spans = Vec::new();
+	tracing_error::SpanTrace::new(span.clone()).with_spans(|meta, fields| {
+		spans.push(CapturedSpan {
+			meta,
+			fields: fields.to_owned(),
+		});
+		true
+	});
+	spans
+}
+
+/// Walks the report's span trace, using the captured one if there is one.
+pub(crate) fn with_spans(
+	span: &Span,
+	handler: Option<&Handler>,
+	mut f: impl FnMut(&'static Metadata<'static>, &str) -> bool,
+) {
+	match handler.and_then(|h| h.captured.as_ref()) {
+		Some(captured) => {
+			for span in captured {
+				if !f(span.meta, &span.fields) {
+					break;
+				}
+			}
+		}
+		None => tracing_error::SpanTrace::new(span.clone()).with_spans(f),
+	}
+}
+
+/// Removes ANSI escape sequences: CSI sequences such as colors and cursor movement,
+/// string sequences such as OSC (terminated by BEL or ST), and other two-character escapes.
+fn strip_ansi(mut s: String) -> String {
+	#[derive(Clone, Copy, PartialEq)]
+	enum State {
+		Text,
+		Escape,
+		Csi,
+		String,
+		StringEscape,
+	}
+	if !s.contains(['\x1B', '\u{9B}']) {
+		return s;
+	}
+	let mut state = State::Text;
+	s.retain(|c| {
+		state = match (state, c) {
+			(State::Text, '\x1B') => State::Escape,
+			(State::Text, '\u{9B}') => State::Csi,
+			(State::Text, _) => return true,
+			(State::Escape, '[') => State::Csi,
+			// OSC, DCS, SOS, PM, and APC are all terminated by ST.
+			(State::Escape, ']' | 'P' | 'X' | '^' | '_') => State::String,
+			// Intermediate bytes, followed by a final byte.
+			(State::Escape, '\x20'..='\x2F') => State::Escape,
+			(State::Escape, _) => State::Text,
+			(State::Csi, '\x40'..='\x7E') => State::Text,
+			(State::Csi, _) => State::Csi,
+			(State::String, '\x07') => State::Text,
+			(State::String, '\x1B') => State::StringEscape,
+			(State::String, _) => State::String,
+			(State::StringEscape, '\\') => State::Text,
+			(State::StringEscape, _) => State::String,
+		};
+		false
+	});
+	s
+}
+
+#[cfg(test)]
+mod tests {
+	use super::strip_ansi;
+
+	fn strip(s: &str) -> String {
+		strip_ansi(s.to_owned())
+	}
+
+	#[test]
+	fn sgr() {
+		assert_eq!(strip("plain"), "plain");
+		assert_eq!(strip("\x1B[1;31mred\x1B[0m text"), "red text");
+		assert_eq!(strip("\u{9B}2mdim\u{9B}m"), "dim");
+		assert_eq!(strip("\x1B(Bcharset"), "charset");
+	}
+
+	#[test]
+	fn hyperlinks() {
+		let st = "\x1B]8;;https://example.com\x1B\\link\x1B]8;;\x1B\\ after";
+		assert_eq!(strip(st), "link after");
+		let bel = "\x1B]8;;https://example.com\x07link\x1B]8;;\x07 after";
+		assert_eq!(strip(bel), "link after");
+	}
+
+	#[test]
+	fn truncated() {
+		assert_eq!(strip("text\x1B"), "text");
+		assert_eq!(strip("text\x1B[1;3"), "text");
+		assert_eq!(strip("text\x1B]8;;https://example.com"), "text");
+		assert_eq!(strip("text\x1B]8;;https://example.com\x1B"), "text");
+	}
+}
@@ -0,0 +1,139 @@
+use std::fmt;
+use std::panic::Location;
+
+use eyre::Report;
+
+/// A collection of several reports, for code that gathers many failures before giving up.
+///
+/// Each report keeps its own span, so they can all be [emitted](Reports::emit_all) in the right place.
+/// The `Display` output shows the number of reports, and the alternate and `Debug` outputs
+/// show each report in full, with its own span trace.
+/// This implements [`std::error::Error`], so it can itself be turned into a [`Report`].
+#[derive(Default)]
+pub struct Reports(Vec<Report>);
+
+impl Reports {
+	/// Creates an empty collection.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a report to the collection.
+	pub fn push(&mut self, report: Report) {
+		self.0.push(report);
+	}
+
+	/// Returns the number of reports.
+	pub fn len(&self) -> usize {
+		self.0.len()
+	}
+
+	/// Returns whether there are no reports.
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// Returns an iterator over the reports.
+	pub fn iter(&self) -> std::slice::Iter<'_, Report> {
+		self.0.iter()
+	}
+
+	/// Returns `Ok(())` if there are no reports, and `Err(self)` otherwise.
+	pub fn into_result(self) -> Result<(), Self> {
+		if self.is_empty() {
+			Ok(())
+		} else {
+			Err(self)
+		}
+	}
+
+	/// Emits each report with [`emit()`](crate::emit()) in its own span,
+	/// followed by a summary event in the current span, with the number of reports in an `error.count` field.
+	///
+	/// The summary is sent at the most severe level of the reports, and is omitted if there are none.
+	#[track_caller]
+	pub fn emit_all(self) {
+		let location = Location::caller();
+		let Some(level) = self.0.iter().map(crate::configured_level).min() else {
+			return;
+		};
+		let count = self.0.len();
+		for report in &self.0 {
+			crate::emit_report_from(report, location);
+		}
+		crate::__event!(
+			target: "eyre_span",
+			level,
+			code.file = location.file(),
+			code.line = location.line(),
+			error.count = count,
+			"{count} errors",
+		);
+	}
+}
+
+impl fmt::Display for Reports {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} errors", self.0.len())?;
+		if f.alternate() {
+			for (i, report) in self.0.iter().enumerate() {
+				write!(f, "\n\nError {i}: {report:#}")?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl fmt::Debug for Reports {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} errors", self.0.len())?;
+		for (i, report) in self.0.iter().enumerate() {
+			write!(f, "\n\nError {i}: {report:?}")?;
+		}
+		Ok(())
+	}
+}
+
+impl std::error::Error for Reports {}
+
+impl From<Vec<Report>> for Reports {
+	fn from(reports: Vec<Report>) -> Self {
+		Self(reports)
+	}
+}
+
+impl From<Reports> for Vec<Report> {
+	fn from(reports: Reports) -> Self {
+		reports.0
+	}
+}
+
+impl FromIterator<Report> for Reports {
+	fn from_iter<I: IntoIterator<Item = Report>>(iter: I) -> Self {
+		Self(iter.into_iter().collect())
+	}
+}
+
+impl Extend<Report> for Reports {
+	fn extend<I: IntoIterator<Item = Report>>(&mut self, iter: I) {
+		self.0.extend(iter)
+	}
+}
+
+impl IntoIterator for Reports {
+	type Item = Report;
+	type IntoIter = std::vec::IntoIter<Report>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+impl<'a> IntoIterator for &'a Reports {
+	type Item = &'a Report;
+	type IntoIter = std::slice::Iter<'a, Report>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.iter()
+	}
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+
+use eyre::Report;
+
+/// A [`Report`] wrapped in a type that implements [`std::error::Error`],
+/// for passing it to APIs that require one, such as `Box<dyn Error>`.
+///
+/// The `Display` output includes the span trace, and the alternate `Display` and `Debug` outputs
+/// are the same as the report's.
+/// Use [`into_report`](SpannedError::into_report) to get the original report back.
+/// A report created from this error with [`Report::from`] or `?` also keeps the wrapped report's span,
+/// and shows the wrapped error in place of this one, so that the span trace is not shown twice.
+pub struct SpannedError(Report);
+
+impl SpannedError {
+	/// Wraps a report.
+	pub fn new(report: Report) -> Self {
+		Self(report)
+	}
+
+	/// Returns the wrapped report.
+	pub fn report(&self) -> &Report {
+		&self.0
+	}
+
+	/// Unwraps the report, with its original span intact.
+	pub fn into_report(self) -> Report {
+		self.0
+	}
+}
+
+impl From<Report> for SpannedError {
+	fn from(report: Report) -> Self {
+		Self(report)
+	}
+}
+
+impl fmt::Display for SpannedError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(&self.0, f)?;
+		// The alternate form of the report already includes the span trace.
+		#[cfg(feature = "tracing-error")]
+		if let Some(handler) = crate::try_handler(&self.0).filter(|_| !f.alternate()) {
+			if handler.config.display_span_trace {
+				f.write_str(handler.rendered.get_or_init(|| handler.render_span_trace()))?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl fmt::Debug for SpannedError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Debug::fmt(&self.0, f)
+	}
+}
+
+impl std::error::Error for SpannedError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		self.0.source()
+	}
+}
+
+/// Returns the error wrapped in a [`SpannedError`], or `error` itself if it is not one.
+pub(crate) fn unwrap<'a>(
+	error: &'a (dyn std::error::Error + 'static),
+) -> &'a (dyn std::error::Error + 'static) {
+	match error.downcast_ref::<SpannedError>() {
+		Some(e) => e.0.as_ref(),
+		None => error,
+	}
+}
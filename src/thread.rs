@@ -0,0 +1,19 @@
+//! Thread spawning that keeps the current span.
+//!
+//! Spans are per-thread, so reports created on a new thread normally have no span at all.
+//! The functions here enter the spawning thread's current span in the new thread,
+//! so that reports created there are placed in the logical parent span.
+
+use std::thread::JoinHandle;
+
+use tracing::Span;
+
+/// Like [`std::thread::spawn`], but runs `f` in the current span.
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+	F: FnOnce() -> T + Send + 'static,
+	T: Send + 'static,
+{
+	let span = Span::current();
+	std::thread::spawn(move || span.in_scope(f))
+}
@@ -0,0 +1,356 @@
+use std::collections::BTreeMap;
+
+use eyre::Report;
+use tracing::Level;
+
+/// An owned snapshot of a [`Report`], for sending errors elsewhere.
+///
+/// Unlike the report itself, this contains only plain data, so it can be stored, compared,
+/// or converted into whatever format an error-tracking service expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportView {
+	/// The report's top-level message.
+	pub message: String,
+	/// The messages of the report's cause chain, starting with the top-level message.
+	pub chain: Vec<String>,
+	/// The span trace, starting with the span the error occurred in.
+	///
+	/// This is always empty if the `tracing-error` feature is disabled.
+	pub spans: Vec<SpanView>,
+}
+
+/// A single entry of a [`ReportView`]'s span trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanView {
+	/// The span's target.
+	pub target: String,
+	/// The span's name.
+	pub name: String,
+	/// The span's level.
+	pub level: Level,
+	/// The span's fields, as formatted by the subscriber.
+	///
+	/// See [`parse_fields`](SpanView::parse_fields) for extracting individual values.
+	pub fields: String,
+}
+
+impl SpanView {
+	/// Parses the span's fields into a map from field names to values.
+	///
+	/// This assumes the `key=value` format used by `tracing_subscriber::fmt`.
+	/// Quoted values have their quotes and escapes removed, so that `id="a b"` gives `a b`,
+	/// while unquoted values extend to the next `key=`.
+	/// A leading value without a key, as written for the `message` field, is stored under `message`.
+	pub fn parse_fields(&self) -> BTreeMap<String, String> {
+		let mut map = BTreeMap::new();
+		let mut s = self.fields.trim_start();
+		if !s.is_empty() && key_len(s).is_none() {
+			let end = value_len(s);
+			map.insert("message".to_owned(), s[..end].to_owned());
+			s = s[end..].trim_start();
+		}
+		while let Some(len) = key_len(s) {
+			let key = &s[..len];
+			s = &s[len + 1..];
+			let value = match parse_quoted(s) {
+				Some((value, len)) => {
+					s = &s[len..];
+					value
+				}
+				None => {
+					let len = value_len(s);
+					let value = s[..len].to_owned();
+					s = &s[len..];
+					value
+				}
+			};
+			map.insert(key.to_owned(), value);
+			s = s.trim_start();
+		}
+		map
+	}
+}
+
+/// Returns the length of the field name at the start of `s`, if it is followed by `=`.
+fn key_len(s: &str) -> Option<usize> {
+	let len = s
+		.find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '.' | '-')))
+		.unwrap_or(s.len());
+	(len != 0 && s[len..].starts_with('=')).then_some(len)
+}
+
+/// Returns the length of the unquoted value at the start of `s`, which ends before the next field.
+fn value_len(s: &str) -> usize {
+	s.match_indices(' ')
+		.map(|(i, _)| i)
+		.find(|&i| key_len(&s[i + 1..]).is_some())
+		.unwrap_or(s.len())
+}
+
+/// Parses a string quoted in the style of `Debug`, returning its contents and the length of the quoted string.
+fn parse_quoted(s: &str) -> Option<(String, usize)> {
+	let mut chars = s.strip_prefix('"')?.char_indices();
+	let mut out = String::new();
+	while let Some((i, c)) = chars.next() {
+		match c {
+			'"' => return Some((out, i + 2)),
+			'\\' => match chars.next()?.1 {
+				'n' => out.push('\n'),
+				'r' => out.push('\r'),
+				't' => out.push('\t'),
+				'0' => out.push('\0'),
+				'u' => {
+					let rest = chars.as_str().strip_prefix('{')?;
+					let end = rest.find('}')?;
+					out.push(char::from_u32(u32::from_str_radix(&rest[..end], 16).ok()?)?);
+					chars.nth(end + 1);
+				}
+				c => out.push(c),
+			},
+			c => out.push(c),
+		}
+	}
+	None
+}
+
+impl ReportView {
+	/// Renders the view as a JSON object, with the same field names as the struct.
+	pub fn to_json(&self) -> String {
+		let mut s = String::new();
+		s.push_str("{\"message\":");
+		write_json_str(&mut s, &self.message);
+		s.push_str(",\"chain\":[");
+		for (i, message) in self.chain.iter().enumerate() {
+			if i != 0 {
+				s.push(',');
+			}
+			write_json_str(&mut s, message);
+		}
+		s.push_str("],\"spans\":[");
+		for (i, span) in self.spans.iter().enumerate() {
+			if i != 0 {
+				s.push(',');
+			}
+			s.push_str("{\"target\":");
+			write_json_str(&mut s, &span.target);
+			s.push_str(",\"name\":");
+			write_json_str(&mut s, &span.name);
+			s.push_str(",\"level\":");
+			write_json_str(&mut s, span.level.as_str());
+			s.push_str(",\"fields\":");
+			write_json_str(&mut s, &span.fields);
+			s.push('}');
+		}
+		s.push_str("]}");
+		s
+	}
+}
+
+fn write_json_str(out: &mut String, s: &str) {
+	use std::fmt::Write;
+	out.push('"');
+	for c in s.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+}
+
+impl From<&Report> for ReportView {
+	/// Panics if the handler was not installed, unless the `graceful` feature is enabled,
+	/// in which case the span trace is empty.
+	fn from(report: &Report) -> Self {
+		ReportView {
+			message: report.to_string(),
+			chain: report.chain().map(|e| e.to_string()).collect(),
+			spans: spans(report),
+		}
+	}
+}
+
+#[cfg_attr(not(feature = "tracing-error"), allow(unused_variables))]
+pub(crate) fn spans(report: &Report) -> Vec<SpanView> {
+	#[allow(unused_mut)]
+	let mut spans = Vec::new();
+	#[cfg(feature = "tracing-error")]
+	{
+		use crate::ReportSpan;
+		crate::render::with_spans(report.span(), crate::try_handler(report), |meta, fields| {
+			spans.push(SpanView {
+				target: meta.target().to_owned(),
+				name: meta.name().to_owned(),
+				level: *meta.level(),
+				fields: crate::render::clean_fields(crate::config(report), fields, false),
+			});
+			true
+		});
+	}
+	spans
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse(fields: &str) -> Vec<(String, String)> {
+		let span = SpanView {
+			target: "target".to_owned(),
+			name: "name".to_owned(),
+			level: Level::INFO,
+			fields: fields.to_owned(),
+		};
+		span.parse_fields().into_iter().collect()
+	}
+
+	fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+		pairs
+			.iter()
+			.map(|&(k, v)| (k.to_owned(), v.to_owned()))
+			.collect()
+	}
+
+	#[test]
+	fn quoted_values() {
+		assert_eq!(parse(r#"a="b c" d=e"#), pairs(&[("a", "b c"), ("d", "e")]));
+		assert_eq!(parse(r#"a="say \"hi\"""#), pairs(&[("a", r#"say "hi""#)]));
+		assert_eq!(parse(r#"a="\\ \n\t""#), pairs(&[("a", "\\ \n\t")]));
+		assert_eq!(
+			parse(r#"a="\u{41}\u{1f600}" b=1"#),
+			pairs(&[("a", "A\u{1f600}"), ("b", "1")])
+		);
+	}
+
+	#[test]
+	fn unquoted_values() {
+		assert_eq!(
+			parse("a=1 b=two words"),
+			pairs(&[("a", "1"), ("b", "two words")])
+		);
+		assert_eq!(
+			parse("a=x=y b=1+1 = 2"),
+			pairs(&[("a", "x=y"), ("b", "1+1 = 2")])
+		);
+		assert_eq!(
+			parse(r#"a="unterminated"#),
+			pairs(&[("a", r#""unterminated"#)])
+		);
+	}
+
+	#[test]
+	fn leading_message() {
+		assert_eq!(
+			parse("hello world a=1"),
+			pairs(&[("a", "1"), ("message", "hello world")])
+		);
+		assert_eq!(parse("= a=1"), pairs(&[("a", "1"), ("message", "=")]));
+		assert_eq!(parse(""), pairs(&[]));
+	}
+
+	#[derive(Debug, PartialEq)]
+	enum Json {
+		Str(String),
+		Arr(Vec<Json>),
+		Obj(Vec<(String, Json)>),
+	}
+
+	/// A minimal JSON parser, covering only what `to_json` produces.
+	fn parse_json(s: &mut &str) -> Json {
+		let c = s.chars().next().unwrap();
+		*s = &s[1..];
+		match c {
+			'"' => {
+				let mut out = String::new();
+				let mut chars = s.chars();
+				loop {
+					match chars.next().unwrap() {
+						'"' => break,
+						'\\' => match chars.next().unwrap() {
+							'u' => {
+								let hex: String = chars.by_ref().take(4).collect();
+								let c = u32::from_str_radix(&hex, 16).unwrap();
+								out.push(char::from_u32(c).unwrap());
+							}
+							'n' => out.push('\n'),
+							'r' => out.push('\r'),
+							't' => out.push('\t'),
+							c @ ('"' | '\\' | '/') => out.push(c),
+							c => panic!("invalid escape {c:?}"),
+						},
+						c => {
+							assert!(!c.is_control(), "unescaped control character {c:?}");
+							out.push(c);
+						}
+					}
+				}
+				*s = chars.as_str();
+				Json::Str(out)
+			}
+			'[' => {
+				let mut items = Vec::new();
+				while !s.starts_with(']') {
+					items.push(parse_json(s));
+					*s = s.strip_prefix(',').unwrap_or(s);
+				}
+				*s = &s[1..];
+				Json::Arr(items)
+			}
+			'{' => {
+				let mut items = Vec::new();
+				while !s.starts_with('}') {
+					let Json::Str(key) = parse_json(s) else {
+						panic!("non-string key")
+					};
+					*s = s.strip_prefix(':').unwrap();
+					items.push((key, parse_json(s)));
+					*s = s.strip_prefix(',').unwrap_or(s);
+				}
+				*s = &s[1..];
+				Json::Obj(items)
+			}
+			c => panic!("unexpected {c:?}"),
+		}
+	}
+
+	#[test]
+	fn to_json_parses() {
+		let tricky = "\"quoted\" back\\slash \u{7}bell \u{1f600}\nline";
+		let view = ReportView {
+			message: tricky.to_owned(),
+			chain: vec![tricky.to_owned(), "cause".to_owned()],
+			spans: vec![SpanView {
+				target: "target".to_owned(),
+				name: "name".to_owned(),
+				level: Level::WARN,
+				fields: tricky.to_owned(),
+			}],
+		};
+		let json = view.to_json();
+		let mut rest = json.as_str();
+		let str = |s: &str| Json::Str(s.to_owned());
+		let expected = Json::Obj(vec![
+			("message".to_owned(), str(tricky)),
+			(
+				"chain".to_owned(),
+				Json::Arr(vec![str(tricky), str("cause")]),
+			),
+			(
+				"spans".to_owned(),
+				Json::Arr(vec![Json::Obj(vec![
+					("target".to_owned(), str("target")),
+					("name".to_owned(), str("name")),
+					("level".to_owned(), str("WARN")),
+					("fields".to_owned(), str(tricky)),
+				])]),
+			),
+		]);
+		assert_eq!(parse_json(&mut rest), expected);
+		assert_eq!(rest, "");
+	}
+}